@@ -1,3 +1,3 @@
 cargo build --release
-python generator.py -s 3 > generated_n-puzzle
+.\target\release\n-puzzle.exe generate 3 --solvable > generated_n-puzzle
 start /B ./target/release/n-puzzle.exe generated_n-puzzle
\ No newline at end of file
@@ -1,4 +1,4 @@
 #!/usr/bin/env bash
 cargo build --release
-python generator.py -s 3 > generated_n-puzzle
+./target/release/n-puzzle generate 3 --solvable > generated_n-puzzle
 ./target/release/n-puzzle generated_n-puzzle
\ No newline at end of file
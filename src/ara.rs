@@ -0,0 +1,396 @@
+use crate::heuristic::Heuristic;
+use crate::node::Node;
+use crate::puzzle::Puzzle;
+use crate::settings::{weighted, Settings};
+use crate::solution::{Improvement, Progress, Solution, SolveError, Statistics, Stop};
+use crate::state::State;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+/*
+ ** Anytime Repairing A* (Likhachev, Gordon, Thrun) : a weighted A* quickly finds a
+ ** first path, then the weight is lowered and the search goes on from where it
+ ** stopped, only reopening the states whose distance improved, until the weight
+ ** reaches 1 or the best path is proven optimal
+ */
+const START_WEIGHT: f64 = 3.0;
+const WEIGHT_STEP: f64 = 0.5;
+
+pub struct Ara<H: Heuristic> {
+    pub open_list: BinaryHeap<Node>,
+    pub closed_list: HashSet<State>,
+    // improved after being closed during the current pass, reopened by the next one
+    pub inconsistent: Vec<Node>,
+    pub distances: HashMap<State, usize>,
+    pub final_state: State,
+    pub best: Option<Node>,
+    pub heuristic: H,
+    pub weight: f64,
+    pub expanded: usize,
+    pub max_states: usize,
+    // states kept at once before giving up
+    pub budget: usize,
+    pub progress: Progress,
+    // for the timeout and cancellation, counted from start_time
+    pub settings: Settings,
+    pub start_time: SystemTime,
+    pub stopped: Option<Stop>,
+}
+
+impl<H: Heuristic> Ara<H> {
+    pub fn new(state: Puzzle, heuristic: H, weight: f64) -> Ara<H> {
+        let mut start_node = Node::new_starting_node(state);
+        start_node.h_score = heuristic.evaluate_state(&start_node.state);
+        start_node.f_score = weighted(0, start_node.h_score, weight);
+        let final_state = State::from(heuristic.goal());
+        let best = Some(start_node.clone()).filter(|node| node.state == final_state);
+
+        let mut distances = HashMap::new();
+        distances.insert(start_node.state.clone(), 0);
+        let mut open_list = BinaryHeap::new();
+        open_list.push(start_node);
+
+        Ara {
+            open_list,
+            closed_list: HashSet::new(),
+            inconsistent: vec![],
+            distances,
+            final_state,
+            best,
+            heuristic,
+            weight,
+            expanded: 0,
+            max_states: 1,
+            budget: usize::MAX,
+            progress: Progress::default(),
+            settings: Settings::default(),
+            start_time: SystemTime::now(),
+            stopped: None,
+        }
+    }
+
+    fn is_outdated(&self, node: &Node) -> bool {
+        self.closed_list.contains(&node.state) || self.distances[&node.state] != node.distance
+    }
+
+    fn best_distance(&self) -> usize {
+        self.best.as_ref().map_or(usize::MAX, |node| node.distance)
+    }
+
+    /*
+     ** expands while some open state could still lead to a path shorter than the
+     ** best one under the current weight
+     */
+    pub fn improve_path(&mut self) {
+        while let Some(node) = self.open_list.peek() {
+            if node.f_score >= self.best_distance() {
+                break;
+            }
+            self.stopped = self.settings.stop(self.start_time);
+            if self.stopped.is_some() {
+                break;
+            }
+            let node = self.open_list.pop().unwrap();
+            if self.is_outdated(&node) {
+                continue;
+            }
+
+            self.closed_list.insert(node.state.clone());
+            self.expanded += 1;
+            self.progress.record(node.distance, node.h_score);
+            for mut child in Node::calculate_next_nodes(Rc::new(node), &self.heuristic) {
+                if let Some(&distance) = self.distances.get(&child.state) {
+                    if distance <= child.distance {
+                        continue;
+                    }
+                }
+                self.distances.insert(child.state.clone(), child.distance);
+                child.f_score = weighted(child.distance, child.h_score, self.weight);
+                if child.state == self.final_state {
+                    self.best = Some(child.clone());
+                }
+                if self.closed_list.contains(&child.state) {
+                    self.inconsistent.push(child);
+                } else {
+                    self.open_list.push(child);
+                }
+            }
+            if self.max_states < self.open_list.len() {
+                self.max_states = self.open_list.len();
+            }
+            if self.distances.len() > self.budget {
+                self.stopped = Some(Stop::OutOfMemory);
+                break;
+            }
+        }
+    }
+
+    /*
+     ** the lowest unweighted f among the states left to expand, no path can be shorter
+     */
+    fn lower_bound(&self) -> Option<usize> {
+        self.open_list
+            .iter()
+            .chain(self.inconsistent.iter())
+            .filter(|node| self.distances[&node.state] == node.distance)
+            .map(|node| node.distance + node.h_score)
+            .min()
+    }
+
+    /*
+     ** how many times longer than the shortest path the best one may be
+     */
+    pub fn suboptimality(&self) -> f64 {
+        let best = self.best_distance() as f64;
+        match self.lower_bound() {
+            Some(bound) if (bound as f64) < best => self.weight.min(best / bound as f64),
+            _ => 1.0,
+        }
+    }
+
+    /*
+     ** every state left to expand goes back in the open list under the new weight
+     */
+    pub fn reweigh(&mut self, weight: f64) {
+        let mut nodes: Vec<Node> = self.open_list.drain().collect();
+        nodes.append(&mut self.inconsistent);
+        self.weight = weight;
+        self.closed_list.clear();
+
+        let mut seen = HashSet::new();
+        for mut node in nodes {
+            if self.distances[&node.state] == node.distance && seen.insert(node.state.clone()) {
+                node.f_score = weighted(node.distance, node.h_score, weight);
+                self.open_list.push(node);
+            }
+        }
+    }
+
+    pub fn statistics(&self, improvements: Vec<Improvement>) -> Statistics {
+        Statistics {
+            time_elapsed: self.start_time.elapsed().unwrap(),
+            open_states: self
+                .open_list
+                .iter()
+                .filter(|node| !self.is_outdated(node))
+                .count(),
+            closed_states: self.expanded,
+            total_states: self.distances.len(),
+            max_states: self.max_states,
+            improvements,
+            progress: self.progress,
+            stopped: self.stopped,
+            ..Statistics::default()
+        }
+    }
+
+    pub fn ara_star(
+        state: Puzzle,
+        heuristic: H,
+        settings: &Settings,
+    ) -> Result<Solution, SolveError> {
+        let mut weight = settings.weight.unwrap_or(START_WEIGHT).max(1.0);
+        let mut ara = Ara::new(state, heuristic, weight);
+        ara.settings = settings.clone();
+        ara.budget = settings.state_budget().unwrap_or(usize::MAX);
+        let mut improvements: Vec<Improvement> = vec![];
+
+        loop {
+            ara.improve_path();
+            let moves = match (&ara.best, ara.stopped) {
+                (Some(best), _) => best.distance,
+                (None, Some(Stop::OutOfMemory)) => return Err(SolveError::OutOfMemory),
+                (None, Some(stop)) => {
+                    return Err(SolveError::Stopped(
+                        stop,
+                        Box::new(ara.statistics(improvements)),
+                    ))
+                }
+                (None, None) => return Err(SolveError::NotFound),
+            };
+            let suboptimality = ara.suboptimality();
+            let time_elapsed = ara.start_time.elapsed().unwrap();
+            // the same path with a tighter bound is reported again, up to proven optimal
+            let improvement = match improvements.last_mut() {
+                Some(last) if last.moves == moves => {
+                    if suboptimality < last.weight {
+                        last.weight = suboptimality;
+                        last.time_elapsed = time_elapsed;
+                        Some(last.clone())
+                    } else {
+                        None
+                    }
+                }
+                _ => {
+                    improvements.push(Improvement {
+                        weight: suboptimality,
+                        moves,
+                        time_elapsed,
+                    });
+                    improvements.last().cloned()
+                }
+            };
+            if let (Some(improvement), Some(report)) = (improvement, settings.on_improvement) {
+                report(&improvement);
+            }
+
+            // stopped early, the best path so far is kept
+            if weight <= 1.0 || suboptimality <= 1.0 || ara.stopped.is_some() {
+                break;
+            }
+            weight = (weight - WEIGHT_STEP).max(1.0);
+            ara.reweigh(weight);
+        }
+
+        let path = ara.best.as_ref().unwrap().retrieve_path();
+        Ok(Solution::new(path, ara.statistics(improvements)))
+    }
+}
+
+#[cfg(test)]
+mod ara_tests {
+    mod ara_star {
+        use crate::ara::*;
+        use crate::graph::Graph;
+        use crate::heuristic::{FromGoal, Manhattan};
+
+        fn puzzle() -> Puzzle {
+            Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            }
+        }
+
+        #[test]
+        fn ends_optimal() {
+            let solution = Ara::ara_star(
+                puzzle(),
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                &Settings::default(),
+            )
+            .unwrap();
+            let improvements = &solution.statistics.improvements;
+
+            assert_eq!(solution.len(), 15);
+            assert_eq!(improvements.last().unwrap().moves, 15);
+            assert_eq!(improvements.last().unwrap().weight, 1.0);
+            assert!(improvements
+                .windows(2)
+                .all(|pair| pair[0].moves > pair[1].moves));
+            assert!(improvements
+                .iter()
+                .all(|improvement| improvement.weight <= START_WEIGHT));
+        }
+
+        #[test]
+        fn first_path_within_the_weight() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let optimal = Graph::a_star(puzzle(), Manhattan::new(&goal), &Settings::default())
+                .unwrap()
+                .len();
+            let mut ara = Ara::new(puzzle(), Manhattan::new(&goal), 2.0);
+            ara.improve_path();
+
+            assert!(ara.best.as_ref().unwrap().distance <= 2 * optimal);
+            assert!(ara.suboptimality() <= 2.0);
+        }
+
+        #[test]
+        fn best_path_so_far_when_stopped() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let mut ara = Ara::new(puzzle(), Manhattan::new(&goal), 3.0);
+            ara.improve_path();
+            let first = ara.best.as_ref().unwrap().distance;
+            ara.settings.cancel.cancel();
+            ara.reweigh(1.0);
+            ara.improve_path();
+
+            assert_eq!(ara.stopped, Some(Stop::Cancelled));
+            assert_eq!(ara.best.as_ref().unwrap().distance, first);
+            assert_eq!(ara.statistics(vec![]).stopped, Some(Stop::Cancelled));
+        }
+
+        #[test]
+        fn cancelled_before_any_path() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let settings = Settings::default();
+            settings.cancel.cancel();
+
+            match Ara::ara_star(puzzle(), Manhattan::new(&goal), &settings) {
+                Err(SolveError::Stopped(Stop::Cancelled, statistics)) => {
+                    assert!(statistics.improvements.is_empty())
+                }
+                result => panic!("{:?}", result),
+            }
+        }
+
+        #[test]
+        fn out_of_memory() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let settings = Settings {
+                max_states: Some(50),
+                ..Settings::default()
+            };
+
+            assert_eq!(
+                Ara::ara_star(puzzle(), Manhattan::new(&goal), &settings).unwrap_err(),
+                SolveError::OutOfMemory
+            );
+        }
+
+        #[test]
+        fn best_path_so_far_out_of_memory() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let mut ara = Ara::new(puzzle(), Manhattan::new(&goal), 3.0);
+            ara.improve_path();
+            let first = ara.best.as_ref().unwrap().distance;
+            let settings = Settings {
+                max_states: Some(ara.distances.len() + 1),
+                ..Settings::default()
+            };
+            let solution = Ara::ara_star(puzzle(), Manhattan::new(&goal), &settings).unwrap();
+
+            assert_eq!(solution.len(), first);
+            assert_eq!(solution.statistics.stopped, Some(Stop::OutOfMemory));
+            assert!(solution.statistics.improvements.last().unwrap().weight <= 3.0);
+        }
+
+        #[test]
+        fn first_path_proven_optimal() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let start = Puzzle {
+                data: vec![3, 5, 2, 1, 4, 6, 8, 7, 0],
+                width: 3,
+                height: 3,
+            };
+            let mut ara = Ara::new(start.clone(), Manhattan::new(&goal), 2.0);
+            ara.improve_path();
+            assert!(ara.suboptimality() > 1.0);
+
+            let settings = Settings {
+                weight: Some(2.0),
+                ..Settings::default()
+            };
+            let solution = Ara::ara_star(start, Manhattan::new(&goal), &settings).unwrap();
+            let improvements = &solution.statistics.improvements;
+
+            assert_eq!(improvements.len(), 1);
+            assert_eq!(improvements[0].moves, ara.best.as_ref().unwrap().distance);
+            assert_eq!(improvements[0].weight, 1.0);
+        }
+
+        #[test]
+        fn already_solved() {
+            let solution = Ara::ara_star(
+                Puzzle::get_final_state(3, 3),
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                &Settings::default(),
+            )
+            .unwrap();
+
+            assert!(solution.is_empty());
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use crate::heuristic::{Blind, FromGoal, Heuristic};
+use crate::node::Node;
+use crate::puzzle::Puzzle;
+use crate::settings::Settings;
+use crate::solution::{Part, Progress, Solution, SolveError, Statistics, Stop};
+use crate::state::State;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+/*
+ ** MM (Holte, Felner, Sharon, Sturtevant) : one A* from the start toward the goal and
+ ** one from the goal toward the start. Both expand in order of max(g + h, 2g), so
+ ** neither goes much past the middle of the shortest path, and the search stops once
+ ** no open state can lead to a shorter path than the best meeting found so far
+ */
+pub struct Frontier {
+    pub open_list: BinaryHeap<Rc<Node>>,
+    pub closed_list: HashSet<State>,
+    // best node known for every state seen from this side, open or closed
+    pub nodes: HashMap<State, Rc<Node>>,
+    pub expanded: usize,
+    // h toward this side's own target
+    pub progress: Progress,
+}
+
+pub struct Bidirectional {
+    pub forward: Frontier,
+    pub backward: Frontier,
+    // (forward node, backward node) of the same state, with the shortest total distance
+    pub best: Option<(Rc<Node>, Rc<Node>)>,
+    pub max_states: usize,
+    // states kept at once, on both sides, before giving up
+    pub budget: usize,
+    // for the timeout and cancellation, counted from start_time
+    pub settings: Settings,
+    pub start_time: SystemTime,
+    pub stopped: Option<Stop>,
+}
+
+fn priority(node: &Node) -> usize {
+    (node.distance + node.h_score).max(2 * node.distance)
+}
+
+fn meeting_distance(meeting: &Option<(Rc<Node>, Rc<Node>)>) -> usize {
+    meeting
+        .as_ref()
+        .map_or(usize::MAX, |(a, b)| a.distance + b.distance)
+}
+
+impl Frontier {
+    fn new<H: Heuristic>(state: Puzzle, heuristic: &H) -> Frontier {
+        let mut start_node = Node::new_starting_node(state);
+        start_node.h_score = heuristic.evaluate_state(&start_node.state);
+        start_node.f_score = priority(&start_node);
+        let start_node = Rc::new(start_node);
+
+        let mut nodes = HashMap::new();
+        nodes.insert(start_node.state.clone(), Rc::clone(&start_node));
+        let mut open_list = BinaryHeap::new();
+        open_list.push(start_node);
+
+        Frontier {
+            open_list,
+            closed_list: HashSet::new(),
+            nodes,
+            expanded: 0,
+            progress: Progress::default(),
+        }
+    }
+
+    pub fn open_count(&self) -> usize {
+        self.nodes.len() - self.closed_list.len()
+    }
+
+    fn is_outdated(&self, node: &Node) -> bool {
+        self.closed_list.contains(&node.state) || self.nodes[&node.state].distance != node.distance
+    }
+
+    /*
+     ** lowest priority left in the open list, outdated copies are dropped on the way
+     */
+    fn top(&mut self) -> Option<usize> {
+        while let Some(node) = self.open_list.peek() {
+            if !self.is_outdated(node) {
+                return Some(node.f_score);
+            }
+            self.open_list.pop();
+        }
+
+        None
+    }
+
+    /*
+     ** expands the best open node, returns the shortest meeting with the other side
+     ** among its children as (node from this side, node from the other one)
+     */
+    fn expand<H: Heuristic>(
+        &mut self,
+        heuristic: &H,
+        other: &Frontier,
+    ) -> Option<(Rc<Node>, Rc<Node>)> {
+        let node = self.open_list.pop()?;
+        self.closed_list.insert(node.state.clone());
+        self.expanded += 1;
+        self.progress.record(node.distance, node.h_score);
+
+        let mut meeting = None;
+        for mut child in Node::calculate_next_nodes(node, heuristic) {
+            if let Some(known) = self.nodes.get(&child.state) {
+                if known.distance <= child.distance {
+                    continue;
+                }
+            }
+            child.f_score = priority(&child);
+            // reopened when an inconsistent heuristic closed it too early
+            self.closed_list.remove(&child.state);
+            let child = Rc::new(child);
+            if let Some(other_node) = other.nodes.get(&child.state) {
+                if child.distance + other_node.distance < meeting_distance(&meeting) {
+                    meeting = Some((Rc::clone(&child), Rc::clone(other_node)));
+                }
+            }
+            self.nodes.insert(child.state.clone(), Rc::clone(&child));
+            self.open_list.push(child);
+        }
+
+        meeting
+    }
+}
+
+impl Bidirectional {
+    pub fn new<F: Heuristic, B: Heuristic>(
+        state: Puzzle,
+        forward_heuristic: &F,
+        backward_heuristic: &B,
+    ) -> Bidirectional {
+        let forward = Frontier::new(state, forward_heuristic);
+        let backward = Frontier::new(forward_heuristic.goal().clone(), backward_heuristic);
+        let start = &forward.open_list.peek().unwrap();
+        let best = backward
+            .nodes
+            .get(&start.state)
+            .map(|goal| (Rc::clone(start), Rc::clone(goal)));
+
+        Bidirectional {
+            forward,
+            backward,
+            best,
+            max_states: 2,
+            budget: usize::MAX,
+            settings: Settings::default(),
+            start_time: SystemTime::now(),
+            stopped: None,
+        }
+    }
+
+    /*
+     ** expands on the side holding the lowest priority, the smaller one on ties,
+     ** until the best meeting is no longer than that priority
+     */
+    pub fn solve<F: Heuristic, B: Heuristic>(
+        &mut self,
+        forward_heuristic: &F,
+        backward_heuristic: &B,
+    ) -> Result<(), SolveError> {
+        loop {
+            let bound = match (self.forward.top(), self.backward.top()) {
+                (Some(forward), Some(backward)) => forward.min(backward),
+                _ => return Ok(()),
+            };
+            if meeting_distance(&self.best) <= bound {
+                return Ok(());
+            }
+            self.stopped = self.settings.stop(self.start_time);
+            if self.stopped.is_some() {
+                return Ok(());
+            }
+
+            let forward_first = self.forward.top() < self.backward.top()
+                || (self.forward.top() == self.backward.top()
+                    && self.forward.open_count() <= self.backward.open_count());
+            let meeting = if forward_first {
+                self.forward.expand(forward_heuristic, &self.backward)
+            } else {
+                self.backward
+                    .expand(backward_heuristic, &self.forward)
+                    .map(|(backward, forward)| (forward, backward))
+            };
+            if meeting_distance(&meeting) < meeting_distance(&self.best) {
+                self.best = meeting;
+            }
+
+            let open_states = self.forward.open_count() + self.backward.open_count();
+            if self.max_states < open_states {
+                self.max_states = open_states;
+            }
+            if self.forward.nodes.len() + self.backward.nodes.len() > self.budget {
+                return Err(SolveError::OutOfMemory);
+            }
+        }
+    }
+
+    /*
+     ** start to the meeting state, then back along the backward side to the goal
+     */
+    pub fn retrieve_path(&self) -> Option<Vec<Puzzle>> {
+        let (forward, backward) = self.best.as_ref()?;
+        let mut path = forward.retrieve_path();
+        let mut second_half = backward.retrieve_path();
+        second_half.reverse();
+        path.extend(second_half.into_iter().skip(1));
+
+        Some(path)
+    }
+
+    pub fn statistics(&self) -> Statistics {
+        Statistics {
+            time_elapsed: self.start_time.elapsed().unwrap(),
+            open_states: self.forward.open_count() + self.backward.open_count(),
+            closed_states: self.forward.closed_list.len() + self.backward.closed_list.len(),
+            total_states: self.forward.nodes.len() + self.backward.nodes.len(),
+            max_states: self.max_states,
+            parts: vec![
+                Part {
+                    name: "forward".to_string(),
+                    expanded_states: self.forward.expanded,
+                },
+                Part {
+                    name: "backward".to_string(),
+                    expanded_states: self.backward.expanded,
+                },
+            ],
+            progress: self.forward.progress.merge(self.backward.progress),
+            stopped: self.stopped,
+            ..Statistics::default()
+        }
+    }
+
+    pub fn mm<H: Heuristic>(
+        state: Puzzle,
+        heuristic: H,
+        settings: &Settings,
+    ) -> Result<Solution, SolveError> {
+        let start_time = SystemTime::now();
+        let backward_heuristic = heuristic
+            .toward(&state)
+            .unwrap_or_else(|| Box::new(Blind::new(&state)));
+        let mut bidirectional = Bidirectional::new(state, &heuristic, &backward_heuristic);
+        bidirectional.settings = settings.clone();
+        bidirectional.start_time = start_time;
+        bidirectional.budget = settings.state_budget().unwrap_or(usize::MAX);
+        bidirectional.solve(&heuristic, &backward_heuristic)?;
+
+        // a meeting found before the stop isn't proven to be the shortest
+        if let Some(stop) = bidirectional.stopped {
+            return Err(SolveError::Stopped(
+                stop,
+                Box::new(bidirectional.statistics()),
+            ));
+        }
+        match bidirectional.retrieve_path() {
+            Some(path) => Ok(Solution::new(path, bidirectional.statistics())),
+            None => Err(SolveError::NotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod bidirectional_tests {
+    mod mm {
+        use crate::bidirectional::*;
+        use crate::generator::{self, Method};
+        use crate::graph::Graph;
+        use crate::heuristic::{Manhattan, ManhattanLinearConflict};
+        use crate::node::Node;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        #[test]
+        fn same_length_as_a_star() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let mut rng = StdRng::seed_from_u64(101);
+            for steps in [0, 1, 2, 5, 30, 101] {
+                let start = generator::generate(&mut rng, &goal, Method::Walk(steps), true);
+                let expected =
+                    Graph::a_star(start.clone(), Manhattan::new(&goal), &Settings::default())
+                        .unwrap();
+                let solution =
+                    Bidirectional::mm(start.clone(), Manhattan::new(&goal), &Settings::default())
+                        .unwrap();
+
+                assert_eq!(solution.len(), expected.len(), "{}", start);
+                assert_eq!(solution.states[0], start);
+                assert_eq!(*solution.states.last().unwrap(), goal);
+                assert!(solution
+                    .states
+                    .windows(2)
+                    .all(|step| Node::calculate_next_states(&step[0]).contains(&step[1])));
+            }
+        }
+
+        #[test]
+        fn blind_backward_side() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let mut rng = StdRng::seed_from_u64(40);
+            let start = generator::generate(&mut rng, &goal, Method::Walk(40), true);
+            let expected =
+                Graph::a_star(start.clone(), Manhattan::new(&goal), &Settings::default()).unwrap();
+            let blind = Blind::new(&start);
+            let mut bidirectional = Bidirectional::new(start, &Manhattan::new(&goal), &blind);
+            bidirectional.solve(&Manhattan::new(&goal), &blind).unwrap();
+
+            assert_eq!(
+                bidirectional.retrieve_path().unwrap().len() - 1,
+                expected.len()
+            );
+        }
+
+        #[test]
+        fn out_of_memory() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let start = Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            };
+            let settings = Settings {
+                max_states: Some(50),
+                ..Settings::default()
+            };
+
+            assert_eq!(
+                Bidirectional::mm(start, Manhattan::new(&goal), &settings).unwrap_err(),
+                SolveError::OutOfMemory
+            );
+        }
+
+        #[test]
+        fn both_sides_expand() {
+            let goal = Puzzle::get_final_state(4, 4);
+            let mut rng = StdRng::seed_from_u64(5);
+            let start = generator::generate(&mut rng, &goal, Method::Walk(60), true);
+            let solution = Bidirectional::mm(
+                start,
+                ManhattanLinearConflict::new(&goal),
+                &Settings::default(),
+            )
+            .unwrap();
+            let parts = &solution.statistics.parts;
+
+            assert_eq!(parts.len(), 2);
+            assert!(parts.iter().all(|part| part.expanded_states > 0));
+        }
+    }
+}
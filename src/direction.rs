@@ -0,0 +1,140 @@
+use crate::puzzle::Puzzle;
+use std::fmt;
+
+/*
+ ** the direction the empty tile moves toward
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/*
+ ** a single move : the tile that slid into the blank, and where the blank went
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub tile: usize,
+    pub direction: Direction,
+}
+
+impl Direction {
+    pub fn from_letter(letter: char) -> Option<Direction> {
+        match letter.to_ascii_uppercase() {
+            'U' => Some(Direction::Up),
+            'D' => Some(Direction::Down),
+            'L' => Some(Direction::Left),
+            'R' => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    pub fn between(from: &Puzzle, to: &Puzzle) -> Option<Direction> {
+        let (x, y) = from.get_position(0);
+        let (x_next, y_next) = to.get_position(0);
+
+        if x == x_next && y_next + 1 == y {
+            Some(Direction::Up)
+        } else if x == x_next && y + 1 == y_next {
+            Some(Direction::Down)
+        } else if y == y_next && x_next + 1 == x {
+            Some(Direction::Left)
+        } else if y == y_next && x + 1 == x_next {
+            Some(Direction::Right)
+        } else {
+            None
+        }
+    }
+}
+
+/*
+ ** U, D, L or R, a solution is then written as a string such as "ULDR"
+ */
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let letter = match self {
+            Direction::Up => 'U',
+            Direction::Down => 'D',
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+        };
+        write!(f, "{}", letter)
+    }
+}
+
+#[cfg(test)]
+mod direction_tests {
+    mod between {
+        use crate::direction::Direction;
+        use crate::puzzle::Puzzle;
+
+        #[test]
+        fn every_direction() {
+            let center = Puzzle::get_final_state(3, 3);
+            let up = Puzzle {
+                data: vec![1, 0, 3, 8, 2, 4, 7, 6, 5],
+                width: 3,
+                height: 3,
+            };
+            let down = Puzzle {
+                data: vec![1, 2, 3, 8, 6, 4, 7, 0, 5],
+                width: 3,
+                height: 3,
+            };
+            let left = Puzzle {
+                data: vec![1, 2, 3, 0, 8, 4, 7, 6, 5],
+                width: 3,
+                height: 3,
+            };
+            let right = Puzzle {
+                data: vec![1, 2, 3, 8, 4, 0, 7, 6, 5],
+                width: 3,
+                height: 3,
+            };
+
+            assert_eq!(Direction::between(&center, &up), Some(Direction::Up));
+            assert_eq!(Direction::between(&center, &down), Some(Direction::Down));
+            assert_eq!(Direction::between(&center, &left), Some(Direction::Left));
+            assert_eq!(Direction::between(&center, &right), Some(Direction::Right));
+        }
+
+        #[test]
+        fn not_a_move() {
+            let center = Puzzle::get_final_state(3, 3);
+            let corner = Puzzle {
+                data: vec![0, 2, 3, 8, 1, 4, 7, 6, 5],
+                width: 3,
+                height: 3,
+            };
+
+            assert_eq!(Direction::between(&center, &corner), None);
+            assert_eq!(Direction::between(&center, &center), None);
+        }
+    }
+
+    mod from_letter {
+        use crate::direction::Direction;
+
+        #[test]
+        fn round_trip() {
+            for direction in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                let letter = direction.to_string().chars().next().unwrap();
+
+                assert_eq!(Direction::from_letter(letter), Some(direction));
+                assert_eq!(
+                    Direction::from_letter(letter.to_ascii_lowercase()),
+                    Some(direction)
+                );
+            }
+            assert_eq!(Direction::from_letter('X'), None);
+        }
+    }
+}
@@ -0,0 +1,470 @@
+use crate::heuristic::{
+    FromGoal, Hamming, Heuristic, LinearConflict, Manhattan, ManhattanLinearConflict,
+};
+use crate::options::HeuristicValues;
+use crate::pattern_database::PatternDatabase;
+use crate::puzzle::Puzzle;
+use crate::walking_distance::WalkingDistance;
+use std::fmt;
+use std::str::FromStr;
+
+/*
+ ** what -h accepts : a heuristic name, max(a,b,...), a sum a+b, or a heuristic
+ ** multiplied by a whole number (2*linear, linear*2)
+ **
+ ** sum     = product ('+' product)*
+ ** product = factor ('*' factor)*
+ ** factor  = number | name | 'max' '(' sum (',' sum)* ')' | '(' sum ')'
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Heuristic(HeuristicValues),
+    Max(Vec<Expression>),
+    Sum(Vec<Expression>),
+    Weighted(usize, Box<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Number(usize),
+    Plus,
+    Times,
+    Comma,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Name(name) => write!(f, "{:?}", name),
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Plus => write!(f, "'+'"),
+            Token::Times => write!(f, "'*'"),
+            Token::Comma => write!(f, "','"),
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_alphabetic() {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphanumeric()) {
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Name(name));
+            continue;
+        }
+        if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                number.push(c);
+                chars.next();
+            }
+            let number = number
+                .parse()
+                .map_err(|_| format!("weight {} is too large", number))?;
+            tokens.push(Token::Number(number));
+            continue;
+        }
+        chars.next();
+        match c {
+            '+' => tokens.push(Token::Plus),
+            '*' => tokens.push(Token::Times),
+            ',' => tokens.push(Token::Comma),
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            c if c.is_whitespace() => {}
+            c => return Err(format!("unexpected character {:?}", c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+enum Factor {
+    Weight(usize),
+    Heuristic(Expression),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(ref token) if *token == expected => Ok(()),
+            Some(token) => Err(format!("expected {}, found {}", expected, token)),
+            None => Err(format!("expected {} at the end", expected)),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expression, String> {
+        let mut terms = vec![self.product()?];
+        while self.peek() == Some(&Token::Plus) {
+            self.next();
+            terms.push(self.product()?);
+        }
+
+        Ok(match terms.len() {
+            1 => terms.pop().unwrap(),
+            _ => Expression::Sum(terms),
+        })
+    }
+
+    // the numbers of a product are its weight, it must hold exactly one heuristic
+    fn product(&mut self) -> Result<Expression, String> {
+        let mut weight = 1usize;
+        let mut heuristic = None;
+        loop {
+            match self.factor()? {
+                Factor::Weight(number) => {
+                    weight = weight
+                        .checked_mul(number)
+                        .ok_or("weight is too large".to_string())?
+                }
+                Factor::Heuristic(expression) if heuristic.is_none() => {
+                    heuristic = Some(expression)
+                }
+                Factor::Heuristic(_) => {
+                    return Err("two heuristics can only be added, not multiplied".into())
+                }
+            }
+            if self.peek() != Some(&Token::Times) {
+                break;
+            }
+            self.next();
+        }
+
+        let heuristic = heuristic.ok_or("a weight must multiply a heuristic".to_string())?;
+        match weight {
+            0 => Err("weights must be positive".into()),
+            1 => Ok(heuristic),
+            _ => Ok(Expression::Weighted(weight, Box::new(heuristic))),
+        }
+    }
+
+    fn factor(&mut self) -> Result<Factor, String> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Factor::Weight(number)),
+            Some(Token::Open) => {
+                let expression = self.sum()?;
+                self.expect(Token::Close)?;
+                Ok(Factor::Heuristic(expression))
+            }
+            Some(Token::Name(ref name)) if name.eq_ignore_ascii_case("max") => {
+                self.expect(Token::Open)?;
+                let mut arguments = vec![self.sum()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.next();
+                    arguments.push(self.sum()?);
+                }
+                self.expect(Token::Close)?;
+                Ok(Factor::Heuristic(match arguments.len() {
+                    1 => arguments.pop().unwrap(),
+                    _ => Expression::Max(arguments),
+                }))
+            }
+            Some(Token::Name(name)) => name
+                .parse::<HeuristicValues>()
+                .map(|value| Factor::Heuristic(Expression::Heuristic(value)))
+                .map_err(|_| {
+                    format!(
+                        "unknown heuristic {:?}, expected one of {}",
+                        name,
+                        HeuristicValues::variants().join(", ").to_lowercase()
+                    )
+                }),
+            Some(token) => Err(format!("unexpected {}", token)),
+            None => Err("unexpected end of the heuristic".into()),
+        }
+    }
+}
+
+impl FromStr for Expression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Expression, String> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            position: 0,
+        };
+        let expression = parser.sum()?;
+        match parser.next() {
+            None => Ok(expression),
+            Some(token) => Err(format!("unexpected {}", token)),
+        }
+    }
+}
+
+impl From<HeuristicValues> for Expression {
+    fn from(value: HeuristicValues) -> Expression {
+        Expression::Heuristic(value)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |expressions: &[Expression], separator: &str| {
+            expressions
+                .iter()
+                .map(|expression| expression.to_string())
+                .collect::<Vec<String>>()
+                .join(separator)
+        };
+
+        match self {
+            Expression::Heuristic(value) => write!(f, "{}", value.to_string().to_lowercase()),
+            Expression::Max(arguments) => write!(f, "max({})", join(arguments, ",")),
+            Expression::Sum(terms) => write!(f, "{}", join(terms, "+")),
+            Expression::Weighted(weight, expression) => match **expression {
+                Expression::Sum(_) => write!(f, "{}*({})", weight, expression),
+                _ => write!(f, "{}*{}", weight, expression),
+            },
+        }
+    }
+}
+
+pub fn build_heuristic(value: HeuristicValues, goal: &Puzzle) -> Box<dyn Heuristic> {
+    match value {
+        HeuristicValues::Hamming => Box::new(Hamming::new(goal)),
+        HeuristicValues::Manhattan => Box::new(Manhattan::new(goal)),
+        HeuristicValues::Linear => Box::new(LinearConflict::new(goal)),
+        HeuristicValues::M2L => Box::new(ManhattanLinearConflict::new(goal)),
+        HeuristicValues::Pdb => Box::new(PatternDatabase::new(goal)),
+        HeuristicValues::Walking => Box::new(WalkingDistance::new(goal)),
+    }
+}
+
+enum Term {
+    Leaf(Box<dyn Heuristic>),
+    Max(Vec<Term>),
+    Sum(Vec<Term>),
+    Weighted(usize, Box<Term>),
+}
+
+impl Term {
+    fn new(expression: &Expression, goal: &Puzzle) -> Term {
+        let build = |expressions: &[Expression]| {
+            expressions
+                .iter()
+                .map(|expression| Term::new(expression, goal))
+                .collect()
+        };
+
+        match expression {
+            Expression::Heuristic(value) => Term::Leaf(build_heuristic(*value, goal)),
+            Expression::Max(arguments) => Term::Max(build(arguments)),
+            Expression::Sum(terms) => Term::Sum(build(terms)),
+            Expression::Weighted(weight, expression) => {
+                Term::Weighted(*weight, Box::new(Term::new(expression, goal)))
+            }
+        }
+    }
+
+    fn evaluate(&self, puzzle: &Puzzle) -> usize {
+        match self {
+            Term::Leaf(heuristic) => heuristic.evaluate(puzzle),
+            Term::Max(arguments) => arguments
+                .iter()
+                .map(|argument| argument.evaluate(puzzle))
+                .max()
+                .unwrap_or(0),
+            Term::Sum(terms) => terms.iter().map(|term| term.evaluate(puzzle)).sum(),
+            Term::Weighted(weight, term) => weight * term.evaluate(puzzle),
+        }
+    }
+
+    /*
+     ** the maximum of admissible heuristics is admissible, a sum or a weight usually
+     ** is not (manhattan+2*linear is, but only because of how both are defined)
+     */
+    fn is_admissible(&self) -> bool {
+        match self {
+            Term::Leaf(heuristic) => heuristic.is_admissible(),
+            Term::Max(arguments) => arguments.iter().all(Term::is_admissible),
+            Term::Sum(terms) => terms.len() == 1 && terms[0].is_admissible(),
+            Term::Weighted(weight, term) => *weight <= 1 && term.is_admissible(),
+        }
+    }
+}
+
+/*
+ ** a heuristic built from an expression, the scores of its parts are not kept in the
+ ** nodes so every state is fully evaluated
+ */
+pub struct Combination {
+    expression: Expression,
+    name: String,
+    goal: Puzzle,
+    root: Term,
+}
+
+impl Combination {
+    pub fn new(expression: &Expression, goal: &Puzzle) -> Combination {
+        Combination {
+            expression: expression.clone(),
+            name: expression.to_string(),
+            goal: goal.clone(),
+            root: Term::new(expression, goal),
+        }
+    }
+}
+
+impl Heuristic for Combination {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_admissible(&self) -> bool {
+        self.root.is_admissible()
+    }
+
+    fn goal(&self) -> &Puzzle {
+        &self.goal
+    }
+
+    fn toward(&self, goal: &Puzzle) -> Option<Box<dyn Heuristic>> {
+        Some(Box::new(Combination::new(&self.expression, goal)))
+    }
+
+    fn evaluate(&self, puzzle: &Puzzle) -> usize {
+        self.root.evaluate(puzzle)
+    }
+}
+
+#[cfg(test)]
+mod expression_tests {
+    mod from_str {
+        use crate::expression::Expression;
+        use crate::options::HeuristicValues;
+
+        fn heuristic(value: HeuristicValues) -> Expression {
+            Expression::Heuristic(value)
+        }
+
+        #[test]
+        fn single_name() {
+            assert_eq!(
+                "manhattan".parse(),
+                Ok(heuristic(HeuristicValues::Manhattan))
+            );
+            assert_eq!("M2L".parse(), Ok(heuristic(HeuristicValues::M2L)));
+        }
+
+        #[test]
+        fn max() {
+            assert_eq!(
+                "max(manhattan, linear)".parse(),
+                Ok(Expression::Max(vec![
+                    heuristic(HeuristicValues::Manhattan),
+                    heuristic(HeuristicValues::Linear),
+                ]))
+            );
+        }
+
+        #[test]
+        fn weighted_sum() {
+            let expected = Ok(Expression::Sum(vec![
+                heuristic(HeuristicValues::Manhattan),
+                Expression::Weighted(2, Box::new(heuristic(HeuristicValues::Linear))),
+            ]));
+
+            assert_eq!("manhattan+2*linear".parse(), expected);
+            assert_eq!("manhattan + linear * 2".parse(), expected);
+        }
+
+        #[test]
+        fn nested() {
+            let expression: Expression = "max(walking,2*(hamming+linear))".parse().unwrap();
+
+            assert_eq!(expression.to_string(), "max(walking,2*(hamming+linear))");
+        }
+
+        #[test]
+        fn errors() {
+            assert!("".parse::<Expression>().is_err());
+            assert!("euclid".parse::<Expression>().is_err());
+            assert!("manhattan+".parse::<Expression>().is_err());
+            assert!("max(manhattan".parse::<Expression>().is_err());
+            assert!("manhattan*linear".parse::<Expression>().is_err());
+            assert!("0*linear".parse::<Expression>().is_err());
+            assert!("2*3".parse::<Expression>().is_err());
+            assert!("manhattan)".parse::<Expression>().is_err());
+        }
+    }
+
+    mod combination {
+        use crate::expression::{Combination, Expression};
+        use crate::heuristic::{FromGoal, Heuristic, Manhattan, ManhattanLinearConflict};
+        use crate::puzzle::Puzzle;
+
+        fn combination(expression: &str) -> Combination {
+            Combination::new(
+                &expression.parse::<Expression>().unwrap(),
+                &Puzzle::get_final_state(3, 3),
+            )
+        }
+
+        #[test]
+        fn same_as_manhattan_linear_conflict() {
+            let puzzle = Puzzle {
+                data: vec![1, 2, 3, 7, 4, 0, 8, 5, 6],
+                width: 3,
+                height: 3,
+            };
+            let goal = Puzzle::get_final_state(3, 3);
+
+            assert_eq!(
+                combination("manhattan+2*linear").evaluate(&puzzle),
+                ManhattanLinearConflict::new(&goal).evaluate(&puzzle)
+            );
+        }
+
+        #[test]
+        fn max_of_two() {
+            let puzzle = Puzzle {
+                data: vec![7, 5, 6, 1, 2, 3, 8, 0, 4],
+                width: 3,
+                height: 3,
+            };
+            let goal = Puzzle::get_final_state(3, 3);
+
+            assert_eq!(
+                combination("max(hamming,manhattan)").evaluate(&puzzle),
+                Manhattan::new(&goal).evaluate(&puzzle)
+            );
+        }
+
+        #[test]
+        fn admissibility() {
+            assert!(combination("max(manhattan,walking)").is_admissible());
+            assert!(!combination("manhattan+linear").is_admissible());
+            assert!(!combination("2*manhattan").is_admissible());
+            assert_eq!(
+                combination("max(manhattan,linear)").name(),
+                "max(manhattan,linear)"
+            );
+        }
+    }
+}
@@ -0,0 +1,179 @@
+extern crate rand;
+use self::rand::seq::SliceRandom;
+use self::rand::Rng;
+use crate::node::Node;
+use crate::puzzle::Puzzle;
+use std::mem;
+
+/*
+ ** how the tiles are laid out, before the board is made solvable or not
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    // every layout as likely
+    Uniform,
+    // that many random moves from the goal, never undoing the previous one
+    Walk(usize),
+}
+
+/*
+ ** flips the parity of the inversions, and with it whether the board is solvable
+ */
+fn swap_two_tiles(puzzle: &mut Puzzle) {
+    let mut tiles = (0..puzzle.data.len()).filter(|&index| puzzle.data[index] != 0);
+    let (first, second) = (tiles.next().unwrap(), tiles.next().unwrap());
+    puzzle.data.swap(first, second);
+}
+
+fn random_walk<R: Rng>(rng: &mut R, goal: &Puzzle, moves: usize) -> Puzzle {
+    let mut puzzle = goal.clone();
+    let mut previous = None;
+    for _ in 0..moves {
+        let next: Vec<Puzzle> = Node::calculate_next_states(&puzzle)
+            .into_iter()
+            .filter(|next| Some(next) != previous.as_ref())
+            .collect();
+        let chosen = next.choose(rng).unwrap().clone();
+        previous = Some(mem::replace(&mut puzzle, chosen));
+    }
+
+    puzzle
+}
+
+/*
+ ** a board of the size of the goal. Swapping the same two tiles pairs every
+ ** unsolvable layout with a solvable one, a uniform layout stays uniform once its
+ ** solvability is set
+ */
+pub fn generate<R: Rng>(rng: &mut R, goal: &Puzzle, method: Method, solvable: bool) -> Puzzle {
+    let mut puzzle = match method {
+        Method::Uniform => {
+            let mut data = goal.data.clone();
+            data.shuffle(rng);
+            Puzzle {
+                data,
+                width: goal.width,
+                height: goal.height,
+            }
+        }
+        Method::Walk(moves) => random_walk(rng, goal, moves),
+    };
+    if Puzzle::is_solvable(puzzle.clone(), goal) != solvable {
+        swap_two_tiles(&mut puzzle);
+    }
+
+    puzzle
+}
+
+/*
+ ** the format parser::parse reads, each comment on a line of its own
+ */
+pub fn to_file(puzzle: &Puzzle, comments: &[String]) -> String {
+    let mut file = String::new();
+    for comment in comments {
+        file += &format!("# {}\n", comment);
+    }
+    if puzzle.width == puzzle.height {
+        file += &format!("{}\n", puzzle.width);
+    } else {
+        file += &format!("{} {}\n", puzzle.width, puzzle.height);
+    }
+    let padding = (puzzle.data.len() - 1).to_string().len();
+    for row in puzzle.data.chunks(puzzle.width) {
+        let row: Vec<String> = row
+            .iter()
+            .map(|tile| format!("{:>1$}", tile, padding))
+            .collect();
+        file += &format!("{}\n", row.join(" "));
+    }
+
+    file
+}
+
+#[cfg(test)]
+mod generator_tests {
+    mod generate {
+        use crate::direction::Direction;
+        use crate::generator::*;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        #[test]
+        fn solvable_or_not() {
+            let mut rng = StdRng::seed_from_u64(42);
+            for goal in [Puzzle::get_final_state(3, 3), Puzzle::get_final_state(4, 3)] {
+                for method in [Method::Uniform, Method::Walk(30)] {
+                    for solvable in [true, false] {
+                        let puzzle = generate(&mut rng, &goal, method, solvable);
+
+                        assert_eq!((puzzle.width, puzzle.height), (goal.width, goal.height));
+                        assert_eq!(Puzzle::is_solvable(puzzle.clone(), &goal), solvable);
+                        let mut tiles = puzzle.data.clone();
+                        tiles.sort_unstable();
+                        assert_eq!(tiles, (0..goal.data.len()).collect::<Vec<_>>());
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn same_seed_same_board() {
+            let goal = Puzzle::get_final_state(4, 4);
+            for method in [Method::Uniform, Method::Walk(100)] {
+                let first = generate(&mut StdRng::seed_from_u64(7), &goal, method, true);
+                let second = generate(&mut StdRng::seed_from_u64(7), &goal, method, true);
+                let other = generate(&mut StdRng::seed_from_u64(8), &goal, method, true);
+
+                assert_eq!(first, second);
+                assert_ne!(first, other);
+            }
+        }
+
+        #[test]
+        fn walk_stays_close_to_the_goal() {
+            let goal = Puzzle::get_final_state(4, 4);
+            let mut rng = StdRng::seed_from_u64(1);
+
+            assert_eq!(generate(&mut rng, &goal, Method::Walk(0), true), goal);
+            let one = generate(&mut rng, &goal, Method::Walk(1), true);
+            assert!(Direction::between(&goal, &one).is_some());
+            let two = generate(&mut rng, &goal, Method::Walk(2), true);
+            assert_ne!(two, goal);
+        }
+    }
+
+    mod to_file {
+        use crate::generator::*;
+        use crate::goal::Goal;
+        use crate::parser;
+
+        fn lines(file: &str) -> Vec<String> {
+            file.lines().map(String::from).collect()
+        }
+
+        #[test]
+        fn square() {
+            let puzzle = Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            };
+            let file = to_file(&puzzle, &[String::from("This puzzle is solvable")]);
+
+            assert_eq!(file, "# This puzzle is solvable\n3\n1 8 4\n0 3 5\n2 7 6\n");
+            assert_eq!(parser::parse(lines(&file), &Goal::Snail), Ok(puzzle));
+        }
+
+        #[test]
+        fn rectangle_read_back() {
+            let goal = Puzzle::get_final_state(5, 3);
+            let puzzle = generate(&mut rand::thread_rng(), &goal, Method::Uniform, true);
+            let file = to_file(&puzzle, &[]);
+
+            assert!(file.starts_with("5 3\n"));
+            // tiles up to 14, right-aligned on 2 characters
+            assert!(file.lines().skip(1).all(|row| row.len() == 5 * 3 - 1));
+            assert_eq!(parser::parse(lines(&file), &Goal::Snail), Ok(puzzle));
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::puzzle::Puzzle;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Goal {
+    #[default]
+    Snail,
+    RowMajor,
+    BlankFirst,
+    Custom(Puzzle),
+}
+
+impl Goal {
+    pub fn state(&self, width: usize, height: usize) -> Option<Puzzle> {
+        match self {
+            Goal::Snail => Some(Puzzle::get_final_state(width, height)),
+            Goal::RowMajor => {
+                let mut data: Vec<usize> = (1..width * height).collect();
+                data.push(0);
+                Some(Puzzle {
+                    data,
+                    width,
+                    height,
+                })
+            }
+            Goal::BlankFirst => Some(Puzzle {
+                data: (0..width * height).collect(),
+                width,
+                height,
+            }),
+            Goal::Custom(puzzle) if puzzle.width == width && puzzle.height == height => {
+                Some(puzzle.clone())
+            }
+            Goal::Custom(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod goal_tests {
+    mod state {
+        use crate::goal::Goal;
+        use crate::puzzle::Puzzle;
+
+        #[test]
+        fn snail() {
+            assert_eq!(Goal::Snail.state(4, 4), Some(Puzzle::get_final_state(4, 4)));
+        }
+
+        #[test]
+        fn row_major() {
+            let expected = Puzzle {
+                data: vec![1, 2, 3, 4, 5, 6, 7, 8, 0],
+                width: 3,
+                height: 3,
+            };
+
+            assert_eq!(Goal::RowMajor.state(3, 3), Some(expected));
+        }
+
+        #[test]
+        fn blank_first() {
+            let expected = Puzzle {
+                data: vec![0, 1, 2, 3, 4, 5, 6, 7, 8],
+                width: 3,
+                height: 3,
+            };
+
+            assert_eq!(Goal::BlankFirst.state(3, 3), Some(expected));
+        }
+
+        #[test]
+        fn custom() {
+            let custom = Puzzle {
+                data: vec![8, 7, 6, 5, 4, 3, 2, 1, 0],
+                width: 3,
+                height: 3,
+            };
+
+            assert_eq!(Goal::Custom(custom.clone()).state(3, 3), Some(custom));
+            assert_eq!(
+                Goal::Custom(Puzzle::get_final_state(4, 4)).state(3, 3),
+                None
+            );
+        }
+
+        #[test]
+        fn rectangular() {
+            let expected = Puzzle {
+                data: vec![1, 2, 3, 4, 5, 0],
+                width: 3,
+                height: 2,
+            };
+
+            assert_eq!(Goal::RowMajor.state(3, 2), Some(expected));
+            assert_eq!(
+                Goal::Custom(Goal::RowMajor.state(3, 2).unwrap()).state(2, 3),
+                None
+            );
+        }
+    }
+}
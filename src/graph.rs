@@ -1,289 +1,834 @@
+use crate::heuristic::{Blind, FromGoal, Heuristic};
 use crate::node::Node;
 use crate::puzzle::Puzzle;
+use crate::settings::{weighted, Settings};
+use crate::solution::{Progress, Solution, SolveError, Statistics};
+use crate::state::State;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
 use std::time::SystemTime;
 
-#[derive(Clone)]
-pub struct Graph {
-    pub open_list: Vec<Node>,
-    pub closed_list: Vec<Node>,
+/*
+ ** what the open list is ordered by
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Priority {
+    // g + weight * h
+    Weighted(f64),
+    // h alone, whatever the distance from the start
+    Greedy,
+}
+
+impl Priority {
+    pub fn f_score(self, distance: usize, h_score: usize) -> usize {
+        match self {
+            Priority::Weighted(weight) => weighted(distance, h_score, weight),
+            Priority::Greedy => h_score,
+        }
+    }
+}
+
+pub struct Graph<H: Heuristic> {
+    pub open_list: BinaryHeap<Node>,
+    pub closed_list: HashSet<State>,
+    pub distances: HashMap<State, usize>,
     pub start_node: Node,
     pub final_node: Node,
-    pub heuristic: fn(Puzzle, Puzzle) -> usize,
+    pub heuristic: H,
     pub max_states: usize,
+    pub priority: Priority,
+    // states kept at once (open and closed) before giving up
+    pub budget: usize,
+    pub progress: Progress,
+    // for the timeout and cancellation
+    pub settings: Settings,
 }
 
-impl Graph {
+impl<H: Heuristic> Graph<H> {
+    pub fn new(state: Puzzle, heuristic: H, priority: Priority) -> Graph<H> {
+        let mut start_node = Node::new_starting_node(state);
+        // children scores are updated from this one
+        start_node.h_score = heuristic.evaluate_state(&start_node.state);
+        start_node.f_score = priority.f_score(0, start_node.h_score);
+
+        Graph {
+            open_list: BinaryHeap::new(),
+            closed_list: HashSet::new(),
+            distances: HashMap::new(),
+            start_node,
+            final_node: Node::get_final_node(heuristic.goal()),
+            heuristic,
+            max_states: 1,
+            priority,
+            budget: usize::MAX,
+            progress: Progress::default(),
+            settings: Settings::default(),
+        }
+    }
+
+    pub fn limited(mut self, settings: &Settings) -> Graph<H> {
+        self.budget = settings.state_budget().unwrap_or(usize::MAX);
+        self.settings = settings.clone();
+        self
+    }
+
+    /*
+     ** what was done so far, when the timeout or a cancellation cut the search short
+     */
+    fn stopped(&self, start_time: SystemTime) -> Option<SolveError> {
+        let stop = self.settings.stop(start_time)?;
+        let mut statistics = self.statistics(start_time);
+        statistics.stopped = Some(stop);
+
+        Some(SolveError::Stopped(stop, Box::new(statistics)))
+    }
+
+    pub fn open_count(&self) -> usize {
+        // the heap may hold outdated copies of a state, the map only the best one
+        self.distances.len() - self.closed_list.len()
+    }
+
     fn add_to_open_list(&mut self, new_node: Node) {
+        self.distances
+            .insert(new_node.state.clone(), new_node.distance);
         self.open_list.push(new_node);
+
+        if self.max_states < self.open_count() {
+            self.max_states = self.open_count();
+        }
     }
 
-    fn add_to_closed_list(&mut self, new_node: Node) {
-        self.closed_list.push(new_node);
+    fn add_to_closed_list(&mut self, new_node: &Node) {
+        self.closed_list.insert(new_node.state.clone());
     }
 
     fn is_lower_cost(&self, node: &Node) -> bool {
-        if self
-            .open_list
-            .iter()
-            .any(|n| n.state == node.state && n.f_score > node.f_score)
-        {
-            return false;
+        match self.distances.get(&node.state) {
+            Some(distance) => node.distance < *distance,
+            None => true,
         }
-        true
     }
 
-    fn update_cost(&mut self, new_node: Node) {
-        let pos = self
-            .open_list
-            .iter()
-            .position(|n| n.state == new_node.state)
-            .unwrap();
-        let old_node = self.open_list[pos].clone();
-        if old_node.f_score > new_node.f_score {
-            self.open_list.remove(pos);
-            self.open_list.insert(
-                self.open_list
-                    .iter()
-                    .position(|n| new_node.f_score > n.f_score)
-                    .unwrap_or_else(|| self.open_list.len()),
-                new_node,
-            );
-        }
+    fn is_outdated(&self, node: &Node) -> bool {
+        self.closed_list.contains(&node.state) || self.distances[&node.state] != node.distance
     }
 
-    fn add_in_sorted_open_list(&mut self, node: Node) {
-        if self.closed_list.contains(&node) {
+    fn add_in_open_list(&mut self, node: Node) {
+        if self.closed_list.contains(&node.state) || !self.is_lower_cost(&node) {
             return;
         }
 
-        if self.open_list.contains(&node) {
-            self.update_cost(node);
-            return;
-        }
+        self.add_to_open_list(node);
+    }
 
-        self.open_list.insert(
-            self.open_list
-                .iter()
-                .position(|n| node.f_score > n.f_score)
-                .unwrap_or_else(|| self.open_list.len()),
-            node,
-        );
-
-        //println!("{:?}", self.open_list);
-        if self.max_states < self.open_list.len() {
-            self.max_states += 1;
+    fn next_nodes(&self, parent: Node) -> Vec<Node> {
+        let mut childs = Node::calculate_next_nodes(Rc::new(parent), &self.heuristic);
+        if self.priority != Priority::Weighted(1.0) {
+            for child in &mut childs {
+                child.f_score = self.priority.f_score(child.distance, child.h_score);
+            }
         }
+
+        childs
     }
 
     fn add_child_nodes_to_open_list(&mut self, parent: Node) {
-        let mut childs =
-            Node::calculate_next_nodes(parent, self.clone().final_node, self.heuristic);
+        let childs = self.next_nodes(parent);
 
-        while !childs.is_empty() {
-            let child = childs.pop();
-            if child != None {
-                self.add_in_sorted_open_list(child.unwrap());
-            }
+        for child in childs {
+            self.add_in_open_list(child);
         }
     }
 
-    pub fn a_star_greedy(state: Puzzle, heuristic: fn(Puzzle, Puzzle) -> usize) {
-        let start_time = SystemTime::now();
-        let mut graph = Graph {
-            open_list: vec![],
-            closed_list: vec![],
-            start_node: Node::new_starting_node(state.clone()),
-            final_node: Node::get_final_node(state.size),
-            heuristic,
-            max_states: 1,
-        };
-        graph.add_to_open_list(graph.start_node.partial_copy());
+    pub fn statistics(&self, start_time: SystemTime) -> Statistics {
+        Statistics {
+            time_elapsed: start_time.elapsed().unwrap(),
+            open_states: self.open_count(),
+            closed_states: self.closed_list.len(),
+            total_states: self.distances.len(),
+            max_states: self.max_states,
+            progress: self.progress,
+            ..Statistics::default()
+        }
+    }
 
-        if graph.recursive_search(graph.clone().start_node, start_time) {
-            return;
-        } else {
-            panic!("Solution not found");
+    fn search(&mut self, start_time: SystemTime) -> Result<Solution, SolveError> {
+        self.add_to_open_list(self.start_node.clone());
+
+        while let Some(curr_node) = self.open_list.pop() {
+            if self.is_outdated(&curr_node) {
+                continue;
+            }
+
+            if let Some(error) = self.stopped(start_time) {
+                return Err(error);
+            }
+            self.progress.record(curr_node.distance, curr_node.h_score);
+
+            if curr_node.state == self.final_node.state {
+                return Ok(Solution::new(
+                    curr_node.retrieve_path(),
+                    self.statistics(start_time),
+                ));
+            }
+
+            self.add_to_closed_list(&curr_node);
+            self.add_child_nodes_to_open_list(curr_node);
+            if self.distances.len() > self.budget {
+                return Err(SolveError::OutOfMemory);
+            }
         }
+        Err(SolveError::NotFound)
     }
 
-    fn recursive_search(&mut self, curr_node: Node, start_time: SystemTime) -> bool {
-        if curr_node == self.final_node {
-            crate::print_result::print_data(self.clone(), curr_node.partial_copy(), start_time);
-            println!("{}", curr_node);
-            return true;
+    /*
+     ** first in first out : every move costs 1, so a state is reached by its shortest
+     ** path the first time it is seen and the goal can be checked as soon as it shows up
+     */
+    fn breadth_first_search(&mut self, start_time: SystemTime) -> Result<Solution, SolveError> {
+        let start_node = self.start_node.clone();
+        if start_node.state == self.final_node.state {
+            return Ok(Solution::new(
+                start_node.retrieve_path(),
+                self.statistics(start_time),
+            ));
         }
-        //println!("{}, score : {}",curr_node.clone(), curr_node.clone().distance + (self.clone().heuristic)(&(curr_node.clone().state)));
-        let mut next_nodes =
-            Node::calculate_next_nodes(curr_node.clone(), self.clone().final_node, self.heuristic);
-        next_nodes.sort_by(|a, b| {
-            (b.clone().distance + (self.heuristic)(b.clone().state, self.clone().final_node.state))
-                .partial_cmp(
-                    &(a.clone().distance
-                        + (self.heuristic)(a.clone().state, self.clone().final_node.state)),
-                )
-                .unwrap()
-        });
-
-        let mut child_node;
-        while !next_nodes.is_empty() {
-            child_node = next_nodes.pop().unwrap();
-
-            if !self.closed_list.contains(&child_node) && self.is_lower_cost(&child_node) {
-                self.add_to_open_list(child_node.partial_copy());
-                if self.recursive_search(child_node, start_time) {
-                    println!("{}", curr_node);
-                    return true;
+        self.distances.insert(start_node.state.clone(), 0);
+        let mut queue = VecDeque::from([start_node]);
+
+        while let Some(curr_node) = queue.pop_front() {
+            if let Some(error) = self.stopped(start_time) {
+                return Err(error);
+            }
+            self.progress.record(curr_node.distance, curr_node.h_score);
+            self.add_to_closed_list(&curr_node);
+            for child in self.next_nodes(curr_node) {
+                if self.distances.contains_key(&child.state) {
+                    continue;
                 }
+                self.distances.insert(child.state.clone(), child.distance);
+                if child.state == self.final_node.state {
+                    return Ok(Solution::new(
+                        child.retrieve_path(),
+                        self.statistics(start_time),
+                    ));
+                }
+                queue.push_back(child);
+            }
+            if self.max_states < queue.len() {
+                self.max_states = queue.len();
+            }
+            if self.distances.len() > self.budget {
+                return Err(SolveError::OutOfMemory);
             }
         }
-
-        self.add_to_closed_list(curr_node.partial_copy());
-        false
+        Err(SolveError::NotFound)
     }
 
-    pub fn a_star(state: Puzzle, heuristic: fn(Puzzle, Puzzle) -> usize) {
+    /*
+     ** best-first on h alone : usually far fewer states than A*, but no bound on the
+     ** length of the path
+     */
+    pub fn greedy(
+        state: Puzzle,
+        heuristic: H,
+        settings: &Settings,
+    ) -> Result<Solution, SolveError> {
         let start_time = SystemTime::now();
+        Graph::new(state, heuristic, Priority::Greedy)
+            .limited(settings)
+            .search(start_time)
+    }
 
-        let mut graph = Graph {
-            open_list: vec![],
-            closed_list: vec![],
-            start_node: Node::new_starting_node(state.clone()),
-            final_node: Node::get_final_node(state.size),
-            heuristic,
-            max_states: 1,
-        };
-        graph.add_to_open_list(graph.start_node.clone());
-
-        let mut curr_node;
-        while !graph.open_list.is_empty() {
-            curr_node = graph.open_list.pop().unwrap();
-
-            if curr_node.state.data == graph.final_node.state.data {
-                crate::print_result::print_solution_with_retrieving(
-                    curr_node.clone(),
-                    graph.clone(),
-                );
-                crate::print_result::print_data(graph.clone(), curr_node.clone(), start_time);
+    /*
+     ** weighted by settings.weight : the path found is at most that many times longer
+     ** than the shortest one
+     */
+    pub fn a_star(
+        state: Puzzle,
+        heuristic: H,
+        settings: &Settings,
+    ) -> Result<Solution, SolveError> {
+        let start_time = SystemTime::now();
+        let priority = Priority::Weighted(settings.weight.unwrap_or(1.0));
+        Graph::new(state, heuristic, priority)
+            .limited(settings)
+            .search(start_time)
+    }
+}
 
-                return;
+/*
+ ** blind baselines : the heuristic is only used for its goal, both find the shortest
+ ** path and show how many states the heuristics save
+ */
+impl Graph<Blind> {
+    /*
+     ** h is 0 everywhere, only the depth reached tells how far the search went
+     */
+    fn without_h_score(result: Result<Solution, SolveError>) -> Result<Solution, SolveError> {
+        match result {
+            Ok(mut solution) => {
+                solution.statistics.progress.best_h_score = None;
+                Ok(solution)
             }
-
-            graph.add_to_closed_list(curr_node.clone());
-            graph.add_child_nodes_to_open_list(curr_node);
+            Err(SolveError::Stopped(stop, mut statistics)) => {
+                statistics.progress.best_h_score = None;
+                Err(SolveError::Stopped(stop, statistics))
+            }
+            error => error,
         }
-        panic!("The graph has been completely explored, yet the goal state hasn't been reached");
+    }
+
+    pub fn breadth_first<H: Heuristic>(
+        state: Puzzle,
+        heuristic: H,
+        settings: &Settings,
+    ) -> Result<Solution, SolveError> {
+        let start_time = SystemTime::now();
+        let blind = Blind::new(heuristic.goal());
+        let result = Graph::new(state, blind, Priority::Weighted(1.0))
+            .limited(settings)
+            .breadth_first_search(start_time);
+        Graph::without_h_score(result)
+    }
+
+    /*
+     ** Dijkstra : A* with h = 0, ordered by the distance from the start
+     */
+    pub fn uniform_cost<H: Heuristic>(
+        state: Puzzle,
+        heuristic: H,
+        settings: &Settings,
+    ) -> Result<Solution, SolveError> {
+        let start_time = SystemTime::now();
+        let blind = Blind::new(heuristic.goal());
+        let result = Graph::new(state, blind, Priority::Weighted(1.0))
+            .limited(settings)
+            .search(start_time);
+        Graph::without_h_score(result)
     }
 }
 
 #[cfg(test)]
 mod graph_tests {
-    /*
-        mod is_lower_cost {
+    mod is_lower_cost {
+        use crate::graph::*;
+        use crate::heuristic::*;
 
-            #[test]
-            fn lower_cost() {
+        #[test]
+        fn lower_cost() {
+            let mut graph = Graph::new(
+                Puzzle::get_final_state(3, 3),
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                Priority::Weighted(1.0),
+            );
+            let state = State::from(&Puzzle {
+                data: vec![1, 2, 3, 8, 4, 0, 7, 6, 5],
+                width: 3,
+                height: 3,
+            });
+
+            graph.add_to_open_list(Node {
+                state: state.clone(),
+                f_score: 5,
+                distance: 4,
+                h_score: 1,
+                parent: None,
+            });
 
-            }
+            assert!(graph.is_lower_cost(&Node {
+                state,
+                f_score: 3,
+                distance: 2,
+                h_score: 1,
+                parent: None,
+            }));
+        }
 
-            #[test]
-            fn not_lower_cost() {
+        #[test]
+        fn not_lower_cost() {
+            let mut graph = Graph::new(
+                Puzzle::get_final_state(3, 3),
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                Priority::Weighted(1.0),
+            );
+            let state = State::from(&Puzzle {
+                data: vec![1, 2, 3, 8, 4, 0, 7, 6, 5],
+                width: 3,
+                height: 3,
+            });
+
+            graph.add_to_open_list(Node {
+                state: state.clone(),
+                f_score: 3,
+                distance: 2,
+                h_score: 1,
+                parent: None,
+            });
 
-            }
+            assert!(!graph.is_lower_cost(&Node {
+                state,
+                f_score: 5,
+                distance: 4,
+                h_score: 1,
+                parent: None,
+            }));
         }
-    */
-    mod add_in_sorted_open_list {
+    }
+
+    mod add_in_open_list {
         use crate::graph::*;
-        use crate::heuristic;
-        use crate::node::*;
-        use crate::puzzle::*;
+        use crate::heuristic::*;
 
         #[test]
         fn dont_add_if_unnecessary() {
-            let mut graph = Graph {
-                open_list: vec![],
-                closed_list: vec![],
-                start_node: Node::new_starting_node(Puzzle {
+            let mut graph = Graph::new(
+                Puzzle {
                     data: vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
-                    size: 3,
-                }),
-                final_node: Node::get_final_node(3),
-                heuristic: heuristic::manhattan_linear_conflict_heuristic,
-                max_states: 1,
-            };
+                    width: 3,
+                    height: 3,
+                },
+                ManhattanLinearConflict::new(&Puzzle::get_final_state(3, 3)),
+                Priority::Weighted(1.0),
+            );
 
             let node1 = Node {
-                state: Puzzle {
+                state: State::from(&Puzzle {
                     data: vec![0, 1, 2, 3, 4, 5, 6, 8, 7],
-                    size: 3,
-                },
+                    width: 3,
+                    height: 3,
+                }),
                 f_score: 1,
                 distance: 2,
+                h_score: 0,
+                parent: None,
             };
 
             let node2 = Node {
-                state: Puzzle {
+                state: State::from(&Puzzle {
                     data: vec![0, 1, 2, 3, 4, 5, 6, 8, 7],
-                    size: 3,
-                },
+                    width: 3,
+                    height: 3,
+                }),
                 f_score: 1,
                 distance: 2,
+                h_score: 0,
+                parent: None,
             };
 
-            graph.add_in_sorted_open_list(node1);
-            graph.add_in_sorted_open_list(node2);
+            graph.add_in_open_list(node1);
+            graph.add_in_open_list(node2);
 
-            assert_eq!(graph.open_list.iter().len(), 1);
+            assert_eq!(graph.open_list.len(), 1);
+            assert_eq!(graph.open_count(), 1);
         }
 
         #[test]
-        fn list_is_sorted() {
-            let mut graph = Graph {
-                open_list: vec![],
-                closed_list: vec![],
-                start_node: Node::new_starting_node(Puzzle {
-                    data: vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
-                    size: 3,
+        fn dont_add_if_closed() {
+            let mut graph = Graph::new(
+                Puzzle::get_final_state(3, 3),
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                Priority::Weighted(1.0),
+            );
+
+            let node = Node {
+                state: State::from(&Puzzle {
+                    data: vec![1, 2, 3, 8, 4, 0, 7, 6, 5],
+                    width: 3,
+                    height: 3,
                 }),
-                final_node: Node::get_final_node(3),
-                heuristic: heuristic::manhattan_distance,
-                max_states: 1,
+                f_score: 1,
+                distance: 1,
+                h_score: 0,
+                parent: None,
             };
 
+            graph.add_in_open_list(node.clone());
+            graph.open_list.pop();
+            graph.add_to_closed_list(&node);
+            graph.add_in_open_list(node);
+
+            assert!(graph.open_list.is_empty());
+            assert_eq!(graph.open_count(), 0);
+        }
+
+        #[test]
+        fn replace_if_lower_cost() {
+            let mut graph = Graph::new(
+                Puzzle::get_final_state(3, 3),
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                Priority::Weighted(1.0),
+            );
+            let state = State::from(&Puzzle {
+                data: vec![1, 2, 3, 8, 4, 0, 7, 6, 5],
+                width: 3,
+                height: 3,
+            });
+
+            graph.add_in_open_list(Node {
+                state: state.clone(),
+                f_score: 7,
+                distance: 6,
+                h_score: 1,
+                parent: None,
+            });
+            graph.add_in_open_list(Node {
+                state: state.clone(),
+                f_score: 3,
+                distance: 2,
+                h_score: 1,
+                parent: None,
+            });
+
+            assert_eq!(graph.open_count(), 1);
+            assert_eq!(graph.distances[&state], 2);
+            let best = graph.open_list.pop().unwrap();
+            assert_eq!(best.distance, 2);
+            assert!(!graph.is_outdated(&best));
+            let outdated = graph.open_list.pop().unwrap();
+            assert!(graph.is_outdated(&outdated));
+        }
+
+        #[test]
+        fn lowest_f_score_first() {
+            let mut graph = Graph::new(
+                Puzzle {
+                    data: vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
+                    width: 3,
+                    height: 3,
+                },
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                Priority::Weighted(1.0),
+            );
+
             let node1 = Node {
-                state: Puzzle {
+                state: State::from(&Puzzle {
                     data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                    size: 3,
-                },
+                    width: 3,
+                    height: 3,
+                }),
                 f_score: 1,
                 distance: 1,
+                h_score: 0,
+                parent: None,
             };
 
             let node2 = Node {
-                state: Puzzle {
+                state: State::from(&Puzzle {
                     data: vec![1, 2, 3, 8, 4, 5, 7, 6, 0],
-                    size: 3,
-                },
+                    width: 3,
+                    height: 3,
+                }),
                 f_score: 5,
                 distance: 3,
+                h_score: 2,
+                parent: None,
             };
 
             let node3 = Node {
-                state: Puzzle {
+                state: State::from(&Puzzle {
                     data: vec![1, 2, 3, 8, 4, 0, 7, 6, 5],
-                    size: 3,
-                },
+                    width: 3,
+                    height: 3,
+                }),
                 f_score: 3,
                 distance: 2,
+                h_score: 1,
+                parent: None,
             };
 
-            graph.add_in_sorted_open_list(node1.clone());
-            graph.add_in_sorted_open_list(node2.clone());
-            graph.add_in_sorted_open_list(node3.clone());
+            graph.add_in_open_list(node1.clone());
+            graph.add_in_open_list(node2.clone());
+            graph.add_in_open_list(node3.clone());
 
             assert_eq!(graph.open_list.len(), 3);
-            assert_eq!(graph.open_list[0], node2);
-            assert_eq!(graph.open_list[1], node3);
-            assert_eq!(graph.open_list[2], node1);
+            assert_eq!(graph.open_list.pop(), Some(node1));
+            assert_eq!(graph.open_list.pop(), Some(node3));
+            assert_eq!(graph.open_list.pop(), Some(node2));
+        }
+
+        #[test]
+        fn ties_go_to_furthest_node() {
+            let mut graph = Graph::new(
+                Puzzle::get_final_state(3, 3),
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                Priority::Weighted(1.0),
+            );
+
+            let near = Node {
+                state: State::from(&Puzzle {
+                    data: vec![1, 2, 3, 8, 4, 0, 7, 6, 5],
+                    width: 3,
+                    height: 3,
+                }),
+                f_score: 4,
+                distance: 1,
+                h_score: 3,
+                parent: None,
+            };
+
+            let far = Node {
+                state: State::from(&Puzzle {
+                    data: vec![1, 2, 3, 8, 4, 5, 7, 6, 0],
+                    width: 3,
+                    height: 3,
+                }),
+                f_score: 4,
+                distance: 3,
+                h_score: 1,
+                parent: None,
+            };
+
+            graph.add_in_open_list(near.clone());
+            graph.add_in_open_list(far.clone());
+
+            assert_eq!(graph.open_list.pop(), Some(far));
+            assert_eq!(graph.open_list.pop(), Some(near));
+        }
+    }
+
+    mod a_star {
+        use crate::generator::{self, Method};
+        use crate::graph::*;
+        use crate::heuristic::*;
+        use crate::solution::Stop;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use std::time::Duration;
+
+        #[test]
+        fn returns_complete_path() {
+            let start = Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            };
+            let path = Graph::a_star(
+                start.clone(),
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                &Settings::default(),
+            )
+            .unwrap()
+            .states;
+
+            assert_eq!(path.len(), 16);
+            assert_eq!(path[0], start);
+            assert_eq!(*path.last().unwrap(), Puzzle::get_final_state(3, 3));
+            assert!(path
+                .windows(2)
+                .all(|step| Node::calculate_next_states(&step[0]).contains(&step[1])));
+        }
+
+        #[test]
+        fn out_of_memory() {
+            let start = Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            };
+            let settings = Settings {
+                max_states: Some(50),
+                ..Settings::default()
+            };
+
+            assert_eq!(
+                Graph::a_star(
+                    start,
+                    Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                    &settings,
+                )
+                .unwrap_err(),
+                SolveError::OutOfMemory
+            );
+        }
+
+        #[test]
+        fn cancelled() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let start = Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            };
+            let settings = Settings::default();
+            settings.cancel.cancel();
+
+            match Graph::a_star(start, Manhattan::new(&goal), &settings) {
+                Err(SolveError::Stopped(Stop::Cancelled, statistics)) => {
+                    assert_eq!(statistics.stopped, Some(Stop::Cancelled));
+                    assert_eq!(statistics.closed_states, 0);
+                }
+                result => panic!("{:?}", result),
+            }
+        }
+
+        #[test]
+        fn timed_out() {
+            let goal = Puzzle::get_final_state(5, 5);
+            let mut rng = StdRng::seed_from_u64(7);
+            let start = generator::generate(&mut rng, &goal, Method::Walk(400), true);
+            let settings = Settings {
+                timeout: Some(Duration::from_millis(50)),
+                ..Settings::default()
+            };
+
+            match Graph::uniform_cost(start, Manhattan::new(&goal), &settings) {
+                Err(SolveError::Stopped(Stop::Timeout, statistics)) => {
+                    assert!(statistics.time_elapsed >= Duration::from_millis(50));
+                    assert!(statistics.closed_states > 0);
+                    assert!(statistics.progress.f_bound > 0);
+                    assert_eq!(statistics.progress.best_h_score, None);
+                }
+                result => panic!("{:?}", result),
+            }
+        }
+
+        #[test]
+        fn weighted_path_within_bound() {
+            let start = Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            };
+            let settings = Settings {
+                weight: Some(2.0),
+                ..Settings::default()
+            };
+            let solution = Graph::a_star(
+                start,
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                &settings,
+            )
+            .unwrap();
+
+            assert!(solution.len() >= 15);
+            assert!(solution.len() <= 30);
+            assert!(solution
+                .states
+                .windows(2)
+                .all(|step| Node::calculate_next_states(&step[0]).contains(&step[1])));
+        }
+    }
+
+    mod blind {
+        use crate::graph::*;
+        use crate::heuristic::*;
+
+        fn puzzle() -> Puzzle {
+            Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            }
+        }
+
+        #[test]
+        fn same_length_as_a_star() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let heuristic = Manhattan::new(&goal);
+            let a_star = Graph::a_star(puzzle(), heuristic.clone(), &Settings::default()).unwrap();
+            let breadth_first =
+                Graph::breadth_first(puzzle(), heuristic.clone(), &Settings::default()).unwrap();
+            let uniform_cost =
+                Graph::uniform_cost(puzzle(), heuristic, &Settings::default()).unwrap();
+
+            assert_eq!(breadth_first.len(), 15);
+            assert_eq!(uniform_cost.len(), 15);
+            assert!(a_star.statistics.closed_states < uniform_cost.statistics.closed_states);
+            for solution in [breadth_first, uniform_cost] {
+                assert_eq!(solution.states[0], puzzle());
+                assert_eq!(*solution.states.last().unwrap(), goal);
+                assert!(solution
+                    .states
+                    .windows(2)
+                    .all(|step| Node::calculate_next_states(&step[0]).contains(&step[1])));
+            }
+        }
+
+        #[test]
+        fn already_solved() {
+            let goal = Puzzle::get_final_state(3, 3);
+
+            assert!(Graph::breadth_first(
+                goal.clone(),
+                Manhattan::new(&goal),
+                &Settings::default()
+            )
+            .unwrap()
+            .is_empty());
+            assert!(
+                Graph::uniform_cost(goal.clone(), Manhattan::new(&goal), &Settings::default())
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+
+        #[test]
+        fn not_found() {
+            let goal = Puzzle::get_final_state(2, 2);
+            let start = Puzzle {
+                data: vec![2, 1, 0, 3],
+                width: 2,
+                height: 2,
+            };
+
+            assert_eq!(
+                Graph::breadth_first(start, Manhattan::new(&goal), &Settings::default())
+                    .unwrap_err(),
+                SolveError::NotFound
+            );
+        }
+    }
+
+    mod greedy {
+        use crate::generator::{self, Method};
+        use crate::graph::*;
+        use crate::heuristic::*;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        #[test]
+        fn ordered_by_h_alone() {
+            assert_eq!(Priority::Greedy.f_score(10, 3), 3);
+            assert_eq!(Priority::Weighted(1.0).f_score(10, 3), 13);
+            assert_eq!(Priority::Weighted(2.5).f_score(10, 3), 17);
+        }
+
+        #[test]
+        fn returns_complete_path() {
+            let goal = Puzzle::get_final_state(4, 4);
+            // scrambled from the goal so that it stays solvable
+            let mut rng = StdRng::seed_from_u64(200);
+            let start = generator::generate(&mut rng, &goal, Method::Walk(200), true);
+            let solution = Graph::greedy(
+                start.clone(),
+                ManhattanLinearConflict::new(&goal),
+                &Settings::default(),
+            )
+            .unwrap();
+
+            assert_eq!(solution.states[0], start);
+            assert_eq!(*solution.states.last().unwrap(), goal);
+            assert!(solution
+                .states
+                .windows(2)
+                .all(|step| Node::calculate_next_states(&step[0]).contains(&step[1])));
+        }
+
+        #[test]
+        fn not_found() {
+            // the other half of the permutations, never reached from this start
+            let goal = Puzzle::get_final_state(2, 2);
+            let start = Puzzle {
+                data: vec![2, 1, 0, 3],
+                width: 2,
+                height: 2,
+            };
+
+            assert_eq!(
+                Graph::greedy(start, Manhattan::new(&goal), &Settings::default()).unwrap_err(),
+                SolveError::NotFound
+            );
         }
     }
 }
@@ -0,0 +1,471 @@
+use crate::heuristic::Heuristic;
+use crate::node::Node;
+use crate::puzzle::Puzzle;
+use crate::settings::Settings;
+use crate::solution::{Part, Progress, Solution, SolveError, Statistics, Stop};
+use crate::state::State;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/*
+ ** Hash Distributed A* (Kishimoto, Fukunaga, Botea) : every state belongs to the
+ ** worker its hash points to, and only that worker keeps its distance and expands it.
+ ** Children owned by another worker are sent to it over a channel. Once a path is
+ ** found, workers keep expanding what could still lead to a shorter one, the search
+ ** is over when they are all idle and no child is left on its way.
+ */
+// how long an idle worker waits for children before checking whether the search is over
+const IDLE_WAIT: Duration = Duration::from_millis(1);
+
+/*
+ ** a child on its way to its owner, the parent is kept to rebuild the path at the end
+ */
+pub struct Message {
+    pub state: State,
+    pub distance: usize,
+    pub h_score: usize,
+    pub parent: Option<State>,
+}
+
+pub struct Hda {
+    // length of the shortest path found so far, usize::MAX until there is one
+    pub best: AtomicUsize,
+    // active workers plus messages sent and not received yet, once it reaches 0
+    // nothing can be expanded anymore
+    pub work: AtomicUsize,
+    pub senders: Vec<Sender<Message>>,
+    // states kept by all the workers, and how many they may keep before giving up
+    pub stored: AtomicUsize,
+    pub budget: usize,
+    // for the timeout and cancellation, the first worker to notice tells the others
+    pub settings: Settings,
+    pub start_time: SystemTime,
+    pub stopped: OnceLock<Stop>,
+}
+
+struct Worker<'a, H: Heuristic> {
+    id: usize,
+    heuristic: &'a H,
+    final_state: State,
+    open_list: BinaryHeap<Node>,
+    // best distance and parent of every state this worker owns
+    parents: HashMap<State, (usize, Option<State>)>,
+    expanded: usize,
+    max_states: usize,
+    progress: Progress,
+    active: bool,
+}
+
+/*
+ ** what is left of a worker once the search is over, its open list can't leave the thread
+ */
+pub struct Table {
+    pub parents: HashMap<State, (usize, Option<State>)>,
+    pub open_states: usize,
+    pub expanded: usize,
+    pub max_states: usize,
+    pub progress: Progress,
+}
+
+pub fn owner(state: &State, workers: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+
+    (hasher.finish() % workers as u64) as usize
+}
+
+impl<'a, H: Heuristic> Worker<'a, H> {
+    fn new(id: usize, heuristic: &'a H) -> Worker<'a, H> {
+        Worker {
+            id,
+            heuristic,
+            final_state: State::from(heuristic.goal()),
+            open_list: BinaryHeap::new(),
+            parents: HashMap::new(),
+            expanded: 0,
+            max_states: 0,
+            progress: Progress::default(),
+            active: true,
+        }
+    }
+
+    /*
+     ** kept only if it is the shortest way to the state seen so far, even if the
+     ** state was already expanded : children don't arrive in order of f
+     */
+    fn receive(&mut self, message: Message, hda: &Hda) {
+        if let Some(&(distance, _)) = self.parents.get(&message.state) {
+            if distance <= message.distance {
+                return;
+            }
+        }
+        let known = self
+            .parents
+            .insert(message.state.clone(), (message.distance, message.parent));
+        if known.is_none() {
+            hda.stored.fetch_add(1, Ordering::SeqCst);
+        }
+        self.open_list.push(Node {
+            state: message.state,
+            distance: message.distance,
+            f_score: message.distance + message.h_score,
+            h_score: message.h_score,
+            parent: None,
+        });
+        if self.max_states < self.open_list.len() {
+            self.max_states = self.open_list.len();
+        }
+    }
+
+    fn accept(&mut self, message: Message, hda: &Hda) {
+        self.receive(message, hda);
+        // an idle worker takes over the share of work the message stood for
+        if self.active {
+            hda.work.fetch_sub(1, Ordering::SeqCst);
+        } else {
+            self.active = true;
+        }
+    }
+
+    /*
+     ** the best open node, if it could still lead to a path shorter than the best one
+     */
+    fn next_node(&mut self, best: usize) -> Option<Node> {
+        while let Some(node) = self.open_list.peek() {
+            if self.parents[&node.state].0 != node.distance {
+                self.open_list.pop();
+            } else if node.f_score < best {
+                return self.open_list.pop();
+            } else {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    fn expand(&mut self, node: Node, hda: &Hda) {
+        if node.state == self.final_state {
+            hda.best.fetch_min(node.distance, Ordering::SeqCst);
+            return;
+        }
+
+        self.expanded += 1;
+        self.progress.record(node.distance, node.h_score);
+        let parent = node.state.clone();
+        for child in Node::calculate_next_nodes(Rc::new(node), self.heuristic) {
+            let message = Message {
+                state: child.state,
+                distance: child.distance,
+                h_score: child.h_score,
+                parent: Some(parent.clone()),
+            };
+            let id = owner(&message.state, hda.senders.len());
+            if id == self.id {
+                self.receive(message, hda);
+            } else {
+                hda.work.fetch_add(1, Ordering::SeqCst);
+                // fails only once the owner stopped, the search is over anyway
+                if hda.senders[id].send(message).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn run(&mut self, receiver: Receiver<Message>, hda: &Hda) {
+        loop {
+            if hda.is_stopped() || hda.is_out_of_memory() {
+                return;
+            }
+            while let Ok(message) = receiver.try_recv() {
+                self.accept(message, hda);
+            }
+
+            if self.active {
+                match self.next_node(hda.best.load(Ordering::SeqCst)) {
+                    Some(node) => self.expand(node, hda),
+                    None => {
+                        self.active = false;
+                        hda.work.fetch_sub(1, Ordering::SeqCst);
+                    }
+                }
+            } else if hda.work.load(Ordering::SeqCst) == 0 {
+                return;
+            } else if let Ok(message) = receiver.recv_timeout(IDLE_WAIT) {
+                self.accept(message, hda);
+            }
+        }
+    }
+
+    fn into_table(self) -> Table {
+        let open_states = self
+            .open_list
+            .iter()
+            .filter(|node| self.parents[&node.state].0 == node.distance)
+            .count();
+
+        Table {
+            parents: self.parents,
+            open_states,
+            expanded: self.expanded,
+            max_states: self.max_states,
+            progress: self.progress,
+        }
+    }
+}
+
+impl Hda {
+    fn is_stopped(&self) -> bool {
+        if self.stopped.get().is_some() {
+            return true;
+        }
+        match self.settings.stop(self.start_time) {
+            Some(stop) => {
+                let _ = self.stopped.set(stop);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn is_out_of_memory(&self) -> bool {
+        self.stored.load(Ordering::SeqCst) > self.budget
+    }
+
+    /*
+     ** follows the parents from the goal back to the start, asking each state's owner
+     */
+    pub fn retrieve_path(tables: &[Table], final_state: &State) -> Vec<Puzzle> {
+        let mut path = vec![];
+        let mut state = Some(final_state.clone());
+        while let Some(current) = state {
+            path.push(Puzzle::from(&current));
+            state = tables[owner(&current, tables.len())].parents[&current]
+                .1
+                .clone();
+        }
+        path.reverse();
+
+        path
+    }
+
+    pub fn statistics(&self, tables: &[Table]) -> Statistics {
+        Statistics {
+            time_elapsed: self.start_time.elapsed().unwrap(),
+            open_states: tables.iter().map(|table| table.open_states).sum(),
+            closed_states: tables.iter().map(|table| table.expanded).sum(),
+            total_states: tables.iter().map(|table| table.parents.len()).sum(),
+            // the workers don't peak at the same time, this is an upper bound
+            max_states: tables.iter().map(|table| table.max_states).sum(),
+            parts: tables
+                .iter()
+                .enumerate()
+                .map(|(id, table)| Part {
+                    name: format!("thread {}", id),
+                    expanded_states: table.expanded,
+                })
+                .collect(),
+            progress: tables.iter().fold(Progress::default(), |progress, table| {
+                progress.merge(table.progress)
+            }),
+            stopped: self.stopped.get().copied(),
+            ..Statistics::default()
+        }
+    }
+
+    pub fn hda_star<H: Heuristic>(
+        state: Puzzle,
+        heuristic: H,
+        settings: &Settings,
+    ) -> Result<Solution, SolveError> {
+        let start_time = SystemTime::now();
+        let workers = settings.thread_count();
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..workers).map(|_| channel()).unzip();
+        let start_state = State::from(&state);
+        let start = Message {
+            h_score: heuristic.evaluate_state(&start_state),
+            state: start_state,
+            distance: 0,
+            parent: None,
+        };
+        senders[owner(&start.state, workers)].send(start).unwrap();
+        let hda = Hda {
+            best: AtomicUsize::new(usize::MAX),
+            // every worker starts active, plus the start message
+            work: AtomicUsize::new(workers + 1),
+            senders,
+            stored: AtomicUsize::new(0),
+            budget: settings.state_budget().unwrap_or(usize::MAX),
+            settings: settings.clone(),
+            start_time,
+            stopped: OnceLock::new(),
+        };
+
+        let tables: Vec<Table> = thread::scope(|scope| {
+            let handles: Vec<_> = receivers
+                .into_iter()
+                .enumerate()
+                .map(|(id, receiver)| {
+                    let (hda, heuristic) = (&hda, &heuristic);
+                    scope.spawn(move || {
+                        let mut worker = Worker::new(id, heuristic);
+                        worker.run(receiver, hda);
+                        worker.into_table()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        if let Some(&stop) = hda.stopped.get() {
+            return Err(SolveError::Stopped(stop, Box::new(hda.statistics(&tables))));
+        }
+        if hda.is_out_of_memory() {
+            return Err(SolveError::OutOfMemory);
+        }
+        if hda.best.load(Ordering::SeqCst) == usize::MAX {
+            return Err(SolveError::NotFound);
+        }
+        let path = Hda::retrieve_path(&tables, &State::from(heuristic.goal()));
+        Ok(Solution::new(path, hda.statistics(&tables)))
+    }
+}
+
+#[cfg(test)]
+mod hda_tests {
+    mod hda_star {
+        use crate::generator::{self, Method};
+        use crate::graph::Graph;
+        use crate::hda::*;
+        use crate::heuristic::{FromGoal, Manhattan, ManhattanLinearConflict};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        fn settings(threads: usize) -> Settings {
+            Settings {
+                threads: Some(threads),
+                ..Settings::default()
+            }
+        }
+
+        fn puzzle() -> Puzzle {
+            Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            }
+        }
+
+        #[test]
+        fn shortest_path_with_any_thread_count() {
+            let goal = Puzzle::get_final_state(3, 3);
+            for threads in [1, 2, 3, 8] {
+                let solution =
+                    Hda::hda_star(puzzle(), Manhattan::new(&goal), &settings(threads)).unwrap();
+
+                assert_eq!(solution.len(), 15);
+                assert_eq!(solution.states[0], puzzle());
+                assert_eq!(*solution.states.last().unwrap(), goal);
+                assert!(solution
+                    .states
+                    .windows(2)
+                    .all(|step| Node::calculate_next_states(&step[0]).contains(&step[1])));
+                assert_eq!(solution.statistics.parts.len(), threads);
+            }
+        }
+
+        #[test]
+        fn same_length_as_a_star_on_fifteen_puzzle() {
+            let goal = Puzzle::get_final_state(4, 4);
+            let mut rng = StdRng::seed_from_u64(4);
+            let start = generator::generate(&mut rng, &goal, Method::Walk(50), true);
+            let expected = Graph::a_star(
+                start.clone(),
+                ManhattanLinearConflict::new(&goal),
+                &Settings::default(),
+            )
+            .unwrap();
+            let solution =
+                Hda::hda_star(start, ManhattanLinearConflict::new(&goal), &settings(4)).unwrap();
+            let parts = &solution.statistics.parts;
+
+            assert_eq!(solution.len(), expected.len());
+            assert_eq!(
+                parts.iter().map(|part| part.expanded_states).sum::<usize>(),
+                solution.statistics.closed_states
+            );
+        }
+
+        #[test]
+        fn timed_out() {
+            let goal = Puzzle::get_final_state(5, 5);
+            let mut rng = StdRng::seed_from_u64(7);
+            let start = generator::generate(&mut rng, &goal, Method::Walk(400), true);
+            let settings = Settings {
+                threads: Some(3),
+                timeout: Some(Duration::from_millis(50)),
+                ..Settings::default()
+            };
+
+            match Hda::hda_star(start, Manhattan::new(&goal), &settings) {
+                Err(SolveError::Stopped(Stop::Timeout, statistics)) => {
+                    assert!(statistics.closed_states > 0);
+                    assert_eq!(statistics.parts.len(), 3);
+                    assert!(statistics.progress.best_h_score.is_some());
+                }
+                result => panic!("{:?}", result),
+            }
+        }
+
+        #[test]
+        fn out_of_memory() {
+            let goal = Puzzle::get_final_state(3, 3);
+            for threads in [1, 3] {
+                let settings = Settings {
+                    max_states: Some(50),
+                    ..settings(threads)
+                };
+
+                assert_eq!(
+                    Hda::hda_star(puzzle(), Manhattan::new(&goal), &settings).unwrap_err(),
+                    SolveError::OutOfMemory
+                );
+            }
+        }
+
+        #[test]
+        fn already_solved() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let solution =
+                Hda::hda_star(goal.clone(), Manhattan::new(&goal), &settings(4)).unwrap();
+
+            assert!(solution.is_empty());
+        }
+
+        #[test]
+        fn not_found() {
+            let goal = Puzzle::get_final_state(2, 2);
+            let start = Puzzle {
+                data: vec![2, 1, 0, 3],
+                width: 2,
+                height: 2,
+            };
+
+            assert_eq!(
+                Hda::hda_star(start, Manhattan::new(&goal), &settings(3)).unwrap_err(),
+                SolveError::NotFound
+            );
+        }
+    }
+}
@@ -1,4 +1,83 @@
+use crate::direction::{Direction, Move};
 use crate::puzzle::Puzzle;
+use crate::state::State;
+
+/*
+ ** a heuristic bound to a goal : whatever it needs (goal positions, tables, pattern
+ ** databases) is set up once, then every state of the search is evaluated against
+ ** that goal. Send and Sync so that the threads of a parallel search can share it
+ */
+pub trait Heuristic: Send + Sync {
+    // the value given to -h
+    fn name(&self) -> &str;
+
+    // never overestimates the number of moves left, A* and IDA* paths are then optimal
+    fn is_admissible(&self) -> bool {
+        true
+    }
+
+    fn goal(&self) -> &Puzzle;
+
+    /*
+     ** the same heuristic bound to another goal, for searches running backward from
+     ** the goal toward the start
+     */
+    fn toward(&self, _goal: &Puzzle) -> Option<Box<dyn Heuristic>> {
+        None
+    }
+
+    fn evaluate(&self, puzzle: &Puzzle) -> usize;
+
+    /*
+     ** score of a child from the score of its parent and the move between them, None
+     ** when only a full evaluation is possible
+     */
+    fn update(&self, _score: usize, _child: &State, _movement: Move) -> Option<usize> {
+        None
+    }
+
+    fn evaluate_state(&self, state: &State) -> usize {
+        self.evaluate(&Puzzle::from(state))
+    }
+
+    fn evaluate_move(&self, score: usize, child: &State, movement: Move) -> usize {
+        self.update(score, child, movement)
+            .unwrap_or_else(|| self.evaluate_state(child))
+    }
+}
+
+impl Heuristic for Box<dyn Heuristic> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn is_admissible(&self) -> bool {
+        (**self).is_admissible()
+    }
+
+    fn goal(&self) -> &Puzzle {
+        (**self).goal()
+    }
+
+    fn toward(&self, goal: &Puzzle) -> Option<Box<dyn Heuristic>> {
+        (**self).toward(goal)
+    }
+
+    fn evaluate(&self, puzzle: &Puzzle) -> usize {
+        (**self).evaluate(puzzle)
+    }
+
+    fn update(&self, score: usize, child: &State, movement: Move) -> Option<usize> {
+        (**self).update(score, child, movement)
+    }
+}
+
+/*
+ ** heuristics that need nothing but the goal to be set up
+ */
+pub trait FromGoal: Heuristic {
+    fn new(goal: &Puzzle) -> Self;
+}
 
 fn get_distance(x1: usize, y1: usize, x2: usize, y2: usize) -> usize {
     let x = (x1 as i32 - x2 as i32).abs();
@@ -7,190 +86,402 @@ fn get_distance(x1: usize, y1: usize, x2: usize, y2: usize) -> usize {
     x as usize + y as usize
 }
 
-fn get_possible_values_in_row(
-    puzzle: Puzzle,
-    final_puzzle: Puzzle,
-    value: usize,
-    y: usize,
-) -> Vec<usize> {
-    let mut values = vec![];
+pub fn goal_positions(final_puzzle: &Puzzle) -> Vec<(usize, usize)> {
+    let mut positions = vec![(0, 0); final_puzzle.data.len()];
+    for (position, &value) in final_puzzle.data.iter().enumerate() {
+        positions[value] = (position % final_puzzle.width, position / final_puzzle.width);
+    }
 
-    let (final_x, _) = final_puzzle.get_position(value);
-    let (current_x, _) = puzzle.get_position(value);
-    if final_x < current_x {
-        for x in (final_x..current_x).rev() {
-            values.push(puzzle.get_value(x, y));
-        }
-    } else {
-        for x in (current_x + 1)..=final_x {
-            values.push(puzzle.get_value(x, y));
-        }
+    positions
+}
+
+/*
+ ** where the blank was before the move and where it is now, the moved tile went
+ ** the other way
+ */
+fn move_positions(child: &State, direction: Direction) -> ((usize, usize), (usize, usize)) {
+    let width = child.width();
+    let (x, y) = (child.blank() % width, child.blank() / width);
+    let previous = match direction {
+        Direction::Up => (x, y + 1),
+        Direction::Down => (x, y - 1),
+        Direction::Left => (x + 1, y),
+        Direction::Right => (x - 1, y),
+    };
+
+    (previous, (x, y))
+}
+
+/*
+ ** 0 everywhere : breadth-first and uniform-cost search, or a side of the
+ ** bidirectional search the real heuristic can't be bound to
+ */
+#[derive(Debug, Clone)]
+pub struct Blind {
+    goal: Puzzle,
+}
+
+impl FromGoal for Blind {
+    fn new(goal: &Puzzle) -> Blind {
+        Blind { goal: goal.clone() }
+    }
+}
+
+impl Heuristic for Blind {
+    fn name(&self) -> &str {
+        "blind"
+    }
+
+    fn goal(&self) -> &Puzzle {
+        &self.goal
+    }
+
+    fn toward(&self, goal: &Puzzle) -> Option<Box<dyn Heuristic>> {
+        Some(Box::new(Blind::new(goal)))
+    }
+
+    fn evaluate(&self, _puzzle: &Puzzle) -> usize {
+        0
     }
 
-    values
+    fn update(&self, _score: usize, _child: &State, _movement: Move) -> Option<usize> {
+        Some(0)
+    }
 }
 
-fn get_possible_values_in_column(
-    puzzle: Puzzle,
-    final_puzzle: Puzzle,
-    value: usize,
-    x: usize,
-) -> Vec<usize> {
-    let mut values = vec![];
+#[derive(Debug, Clone)]
+pub struct Hamming {
+    goal: Puzzle,
+    goal_positions: Vec<(usize, usize)>,
+}
 
-    let (_, final_y) = final_puzzle.get_position(value);
-    let (_, current_y) = puzzle.get_position(value);
-    if final_y < current_y {
-        for y in (final_y..current_y).rev() {
-            values.push(puzzle.get_value(x, y));
+impl FromGoal for Hamming {
+    fn new(goal: &Puzzle) -> Hamming {
+        Hamming {
+            goal: goal.clone(),
+            goal_positions: goal_positions(goal),
         }
-    } else {
-        for y in (current_y + 1)..=final_y {
-            values.push(puzzle.get_value(x, y));
+    }
+}
+
+impl Heuristic for Hamming {
+    fn name(&self) -> &str {
+        "hamming"
+    }
+
+    fn goal(&self) -> &Puzzle {
+        &self.goal
+    }
+
+    fn toward(&self, goal: &Puzzle) -> Option<Box<dyn Heuristic>> {
+        Some(Box::new(Hamming::new(goal)))
+    }
+
+    fn evaluate(&self, puzzle: &Puzzle) -> usize {
+        // +1 per misplaced tiles (except empty one)
+        let mut heuristic = 0;
+
+        for y in 0..puzzle.height {
+            for x in 0..puzzle.width {
+                let value = puzzle.get_value(x, y);
+                if value != 0 && self.goal_positions[value] != (x, y) {
+                    heuristic += 1;
+                }
+            }
         }
+
+        heuristic
     }
 
-    values
+    fn update(&self, score: usize, child: &State, movement: Move) -> Option<usize> {
+        let (before, after) = move_positions(child, movement.direction);
+        let goal = self.goal_positions[movement.tile];
+
+        Some(score + (before != goal) as usize - (after != goal) as usize)
+    }
 }
 
-fn check_in_correct_column(puzzle: Puzzle, value: usize, x: usize) -> bool {
-    for y in 0..puzzle.size {
-        if puzzle.get_value(x, y) == value {
-            return true;
+pub fn manhattan_distance(puzzle: &Puzzle, goal_positions: &[(usize, usize)]) -> usize {
+    // +1 per move a misplaced tile as to do (except empty one)
+    let mut heuristic = 0;
+
+    for y in 0..puzzle.height {
+        for x in 0..puzzle.width {
+            let value = puzzle.get_value(x, y);
+            if value != 0 {
+                let (x2, y2) = goal_positions[value];
+                heuristic += get_distance(x, y, x2, y2);
+            }
         }
     }
 
-    false
+    heuristic
 }
 
-fn check_in_correct_row(puzzle: Puzzle, value: usize, y: usize) -> bool {
-    for x in 0..puzzle.size {
-        if puzzle.get_value(x, y) == value {
-            return true;
+fn manhattan_update(
+    score: usize,
+    child: &State,
+    movement: Move,
+    goal_positions: &[(usize, usize)],
+) -> usize {
+    // the tile goes from where the blank is now to where it was
+    let (before, after) = move_positions(child, movement.direction);
+    let (goal_x, goal_y) = goal_positions[movement.tile];
+
+    score + get_distance(before.0, before.1, goal_x, goal_y)
+        - get_distance(after.0, after.1, goal_x, goal_y)
+}
+
+#[derive(Debug, Clone)]
+pub struct Manhattan {
+    goal: Puzzle,
+    goal_positions: Vec<(usize, usize)>,
+}
+
+impl FromGoal for Manhattan {
+    fn new(goal: &Puzzle) -> Manhattan {
+        Manhattan {
+            goal: goal.clone(),
+            goal_positions: goal_positions(goal),
         }
     }
+}
+
+impl Heuristic for Manhattan {
+    fn name(&self) -> &str {
+        "manhattan"
+    }
+
+    fn goal(&self) -> &Puzzle {
+        &self.goal
+    }
 
-    false
+    fn toward(&self, goal: &Puzzle) -> Option<Box<dyn Heuristic>> {
+        Some(Box::new(Manhattan::new(goal)))
+    }
+
+    fn evaluate(&self, puzzle: &Puzzle) -> usize {
+        manhattan_distance(puzzle, &self.goal_positions)
+    }
+
+    fn update(&self, score: usize, child: &State, movement: Move) -> Option<usize> {
+        Some(manhattan_update(
+            score,
+            child,
+            movement,
+            &self.goal_positions,
+        ))
+    }
 }
 
-fn check_column_conflict(
-    current_puzzle: Puzzle,
-    final_puzzle: Puzzle,
-    value: usize,
-    x: usize,
-    number_list: &[usize],
-) -> usize {
-    let possible_values =
-        get_possible_values_in_column(current_puzzle, final_puzzle.clone(), value, x);
-    for possible_value in possible_values {
-        if check_in_correct_column(final_puzzle.clone(), possible_value, x)
-            && number_list.contains(&possible_value)
-            && possible_value != 0
-        {
-            return 1;
+/*
+ ** minimum number of tiles to take out of a line so that the ones left are in goal
+ ** order : the length minus the longest increasing subsequence of their goal positions
+ */
+fn line_conflicts(goal_positions: &[usize]) -> usize {
+    let mut tails: Vec<usize> = vec![];
+
+    for &position in goal_positions {
+        let index = tails.partition_point(|&tail| tail < position);
+        if index == tails.len() {
+            tails.push(position);
+        } else {
+            tails[index] = position;
         }
     }
-    0
+
+    goal_positions.len() - tails.len()
 }
 
-fn check_row_conflict(
-    current_puzzle: Puzzle,
-    final_puzzle: Puzzle,
-    value: usize,
+fn row_conflicts(
+    get: impl Fn(usize, usize) -> usize,
+    width: usize,
+    goal_positions: &[(usize, usize)],
     y: usize,
-    number_list: &[usize],
 ) -> usize {
-    let possible_values =
-        get_possible_values_in_row(current_puzzle, final_puzzle.clone(), value, y);
-    for possible_value in possible_values {
-        if check_in_correct_row(final_puzzle.clone(), possible_value, y)
-            && number_list.contains(&possible_value)
-            && possible_value != 0
-        {
-            return 1;
-        }
+    let in_goal_row: Vec<usize> = (0..width)
+        .map(|x| get(x, y))
+        .filter(|&value| value != 0 && goal_positions[value].1 == y)
+        .map(|value| goal_positions[value].0)
+        .collect();
+
+    line_conflicts(&in_goal_row)
+}
+
+fn column_conflicts(
+    get: impl Fn(usize, usize) -> usize,
+    height: usize,
+    goal_positions: &[(usize, usize)],
+    x: usize,
+) -> usize {
+    let in_goal_column: Vec<usize> = (0..height)
+        .map(|y| get(x, y))
+        .filter(|&value| value != 0 && goal_positions[value].0 == x)
+        .map(|value| goal_positions[value].1)
+        .collect();
+
+    line_conflicts(&in_goal_column)
+}
+
+/*
+ ** conflicts of every row and column, each one costs two extra moves
+ */
+fn linear_conflicts(puzzle: &Puzzle, goal_positions: &[(usize, usize)]) -> usize {
+    let get = |x, y| puzzle.get_value(x, y);
+    let rows: usize = (0..puzzle.height)
+        .map(|y| row_conflicts(get, puzzle.width, goal_positions, y))
+        .sum();
+    let columns: usize = (0..puzzle.width)
+        .map(|x| column_conflicts(get, puzzle.height, goal_positions, x))
+        .sum();
+
+    rows + columns
+}
+
+/*
+ ** conflicts of the two lines the tile went between, after and before the move
+ */
+fn linear_conflict_change(
+    child: &State,
+    movement: Move,
+    goal_positions: &[(usize, usize)],
+) -> (usize, usize) {
+    let (width, height) = (child.width(), child.height());
+    let (before, after) = move_positions(child, movement.direction);
+    let child_get = |x, y| child.get(y * width + x);
+    let parent_get = |x, y| {
+        if (x, y) == after {
+            movement.tile
+        } else if (x, y) == before {
+            0
+        } else {
+            child.get(y * width + x)
+        }
+    };
+
+    match movement.direction {
+        Direction::Up | Direction::Down => [before.1, after.1]
+            .iter()
+            .map(|&y| {
+                (
+                    row_conflicts(child_get, width, goal_positions, y),
+                    row_conflicts(parent_get, width, goal_positions, y),
+                )
+            })
+            .fold((0, 0), |(a, b), (c, d)| (a + c, b + d)),
+        Direction::Left | Direction::Right => [before.0, after.0]
+            .iter()
+            .map(|&x| {
+                (
+                    column_conflicts(child_get, height, goal_positions, x),
+                    column_conflicts(parent_get, height, goal_positions, x),
+                )
+            })
+            .fold((0, 0), |(a, b), (c, d)| (a + c, b + d)),
     }
-    0
 }
 
-pub fn hamming_distance(puzzle: Puzzle, final_puzzle: Puzzle) -> usize {
-    // +1 per misplaced tiles (except empty one)
-    let mut heuristic = 0;
+#[derive(Debug, Clone)]
+pub struct LinearConflict {
+    goal: Puzzle,
+    goal_positions: Vec<(usize, usize)>,
+}
 
-    for y in 0..puzzle.size {
-        for x in 0..puzzle.size {
-            if final_puzzle.get_value(x, y) != puzzle.get_value(x, y) && puzzle.get_value(x, y) != 0
-            {
-                heuristic += 1;
-            }
+impl FromGoal for LinearConflict {
+    fn new(goal: &Puzzle) -> LinearConflict {
+        LinearConflict {
+            goal: goal.clone(),
+            goal_positions: goal_positions(goal),
         }
     }
-
-    heuristic
 }
 
-pub fn manhattan_distance(puzzle: Puzzle, final_puzzle: Puzzle) -> usize {
-    // +1 per move a misplaced tile as to do (except empty one)
-    let mut heuristic = 0;
+impl Heuristic for LinearConflict {
+    fn name(&self) -> &str {
+        "linear"
+    }
 
-    for y in 0..puzzle.size {
-        for x in 0..puzzle.size {
-            if final_puzzle.get_value(x, y) != puzzle.get_value(x, y) && puzzle.get_value(x, y) != 0
-            {
-                let value = puzzle.get_value(x, y);
-                let (x2, y2) = final_puzzle.get_position(value);
-                heuristic += get_distance(x, y, x2, y2);
-            }
-        }
+    fn goal(&self) -> &Puzzle {
+        &self.goal
     }
 
-    heuristic
+    fn toward(&self, goal: &Puzzle) -> Option<Box<dyn Heuristic>> {
+        Some(Box::new(LinearConflict::new(goal)))
+    }
+
+    fn evaluate(&self, puzzle: &Puzzle) -> usize {
+        // +1 per tile to take out of its goal row or column so the others are in goal order (except empty one)
+        linear_conflicts(puzzle, &self.goal_positions)
+    }
+
+    fn update(&self, score: usize, child: &State, movement: Move) -> Option<usize> {
+        let (after, before) = linear_conflict_change(child, movement, &self.goal_positions);
+
+        Some(score + after - before)
+    }
 }
 
-pub fn linear_conflict(puzzle: Puzzle, final_puzzle: Puzzle) -> usize {
-    // +2 when two tiles are in their goal row or column, but are reversed relative to their goal positions.  (except empty one)
-    let mut heuristic = 0;
-    let size = puzzle.size;
-    let mut number_list: Vec<usize> = (0..(size * size)).collect();
-
-    for y in 0..size {
-        for x in 0..size {
-            let final_value = &final_puzzle.get_value(x, y);
-            let value = &puzzle.get_value(x, y);
-            if final_value != value && *value != 0 {
-                let conflict_value = check_column_conflict(
-                    puzzle.clone(),
-                    final_puzzle.clone(),
-                    *value,
-                    x,
-                    &number_list,
-                ) + check_row_conflict(
-                    puzzle.clone(),
-                    final_puzzle.clone(),
-                    *value,
-                    y,
-                    &number_list,
-                );
-                if conflict_value != 0usize {
-                    heuristic += conflict_value;
-                    let index = number_list.iter().position(|x| *x == *value).unwrap();
-                    number_list.remove(index);
-                }
-            }
+/*
+ ** manhattan distance, plus two moves per linear conflict
+ */
+#[derive(Debug, Clone)]
+pub struct ManhattanLinearConflict {
+    goal: Puzzle,
+    goal_positions: Vec<(usize, usize)>,
+}
+
+impl FromGoal for ManhattanLinearConflict {
+    fn new(goal: &Puzzle) -> ManhattanLinearConflict {
+        ManhattanLinearConflict {
+            goal: goal.clone(),
+            goal_positions: goal_positions(goal),
         }
     }
-
-    heuristic
 }
 
-pub fn manhattan_linear_conflict_heuristic(puzzle: Puzzle, final_puzzle: Puzzle) -> usize {
-    manhattan_distance(puzzle.clone(), final_puzzle.clone())
-        + 2 * linear_conflict(puzzle, final_puzzle)
+impl Heuristic for ManhattanLinearConflict {
+    fn name(&self) -> &str {
+        "m2l"
+    }
+
+    fn goal(&self) -> &Puzzle {
+        &self.goal
+    }
+
+    fn toward(&self, goal: &Puzzle) -> Option<Box<dyn Heuristic>> {
+        Some(Box::new(ManhattanLinearConflict::new(goal)))
+    }
+
+    fn evaluate(&self, puzzle: &Puzzle) -> usize {
+        manhattan_distance(puzzle, &self.goal_positions)
+            + 2 * linear_conflicts(puzzle, &self.goal_positions)
+    }
+
+    fn update(&self, score: usize, child: &State, movement: Move) -> Option<usize> {
+        let (after, before) = linear_conflict_change(child, movement, &self.goal_positions);
+
+        Some(
+            manhattan_update(score, child, movement, &self.goal_positions) + 2 * after - 2 * before,
+        )
+    }
 }
 
 #[cfg(test)]
 mod heuristic_tests {
-    mod hamming_distance {
+    // every arrangement of data, the ones before start stay in place
+    fn permutations(data: &mut [usize], start: usize, check: &mut dyn FnMut(&[usize])) {
+        if start == data.len() {
+            check(data);
+            return;
+        }
+        for i in start..data.len() {
+            data.swap(start, i);
+            permutations(data, start + 1, check);
+            data.swap(start, i);
+        }
+    }
+
+    mod hamming {
         use super::super::Puzzle;
         use crate::heuristic::*;
 
@@ -198,8 +489,12 @@ mod heuristic_tests {
         fn five_moved_tiles() {
             let size = 3;
             let data = vec![1, 2, 8, 0, 3, 4, 7, 5, 6];
-            let puzzle = Puzzle { data, size };
-            let heuristic = hamming_distance(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic = Hamming::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 4);
         }
@@ -208,8 +503,12 @@ mod heuristic_tests {
         fn no_moved_tile() {
             let size = 3;
             let data = vec![1, 2, 3, 8, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let heuristic = hamming_distance(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic = Hamming::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 0);
         }
@@ -218,14 +517,18 @@ mod heuristic_tests {
         fn every_tiles_moved() {
             let size = 3;
             let data = vec![7, 5, 6, 1, 2, 3, 8, 0, 4];
-            let puzzle = Puzzle { data, size };
-            let heuristic = hamming_distance(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic = Hamming::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 8);
         }
     }
 
-    mod manhattan_distance {
+    mod manhattan {
         use super::super::Puzzle;
         use crate::heuristic::*;
 
@@ -233,8 +536,12 @@ mod heuristic_tests {
         fn five_moved_tiles() {
             let size = 3;
             let data = vec![1, 2, 8, 0, 3, 4, 7, 5, 6];
-            let puzzle = Puzzle { data, size };
-            let heuristic = manhattan_distance(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic = Manhattan::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 7);
         }
@@ -243,8 +550,12 @@ mod heuristic_tests {
         fn no_moved_tile() {
             let size = 3;
             let data = vec![1, 2, 3, 8, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let heuristic = manhattan_distance(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic = Manhattan::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 0);
         }
@@ -253,8 +564,12 @@ mod heuristic_tests {
         fn every_tiles_moved() {
             let size = 3;
             let data = vec![7, 5, 6, 1, 2, 3, 8, 0, 4];
-            let puzzle = Puzzle { data, size };
-            let heuristic = manhattan_distance(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic = Manhattan::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 13);
         }
@@ -268,8 +583,13 @@ mod heuristic_tests {
         fn one_conflict_in_one_row() {
             let size = 3;
             let data = vec![2, 1, 3, 8, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let heuristic = linear_conflict(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic =
+                LinearConflict::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 1);
         }
@@ -278,8 +598,13 @@ mod heuristic_tests {
         fn two_conflicts_in_one_row() {
             let size = 3;
             let data = vec![3, 2, 1, 8, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let heuristic = linear_conflict(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic =
+                LinearConflict::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 2);
         }
@@ -288,8 +613,13 @@ mod heuristic_tests {
         fn one_conflict_in_one_column() {
             let size = 3;
             let data = vec![8, 2, 3, 1, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let heuristic = linear_conflict(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic =
+                LinearConflict::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 1);
         }
@@ -298,8 +628,13 @@ mod heuristic_tests {
         fn two_conflicts_in_one_column() {
             let size = 3;
             let data = vec![7, 2, 3, 8, 0, 4, 1, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let heuristic = linear_conflict(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic =
+                LinearConflict::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 2);
         }
@@ -308,352 +643,211 @@ mod heuristic_tests {
         fn no_conflict() {
             let size = 3;
             let data = vec![1, 2, 3, 8, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let heuristic = linear_conflict(puzzle, Puzzle::get_final_state(size));
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
+            let heuristic =
+                LinearConflict::new(&Puzzle::get_final_state(size, size)).evaluate(&puzzle);
 
             assert_eq!(heuristic, 0);
         }
     }
 
-    mod check_in_correct_column {
-        use super::super::Puzzle;
-        use crate::heuristic::*;
+    mod line_conflicts {
+        use crate::heuristic::line_conflicts;
 
         #[test]
-        fn return_true() {
-            let size = 3;
-            let data = vec![8, 2, 3, 1, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let x = 0;
-            let value = 1;
-            let in_correct_column =
-                check_in_correct_column(Puzzle::get_final_state(puzzle.size), value, x);
-
-            assert!(in_correct_column);
+        fn in_order() {
+            assert_eq!(line_conflicts(&[0, 1, 3]), 0);
+            assert_eq!(line_conflicts(&[]), 0);
         }
 
         #[test]
-        fn return_false() {
-            let size = 3;
-            let data = vec![2, 1, 3, 8, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let x = 1;
-            let value = 1;
-            let in_correct_column =
-                check_in_correct_column(Puzzle::get_final_state(puzzle.size), value, x);
-
-            assert!(!in_correct_column);
+        fn minimum_removal() {
+            // taking 0 out is enough, a tile by tile count would see 2 conflicts for it
+            assert_eq!(line_conflicts(&[3, 0, 1, 2]), 1);
+            assert_eq!(line_conflicts(&[3, 2, 1, 0]), 3);
+            assert_eq!(line_conflicts(&[1, 0, 3, 2]), 2);
         }
     }
 
-    mod check_in_correct_row {
-        use super::super::Puzzle;
+    mod exhaustive {
+        use super::permutations;
         use crate::heuristic::*;
+        use crate::state::State;
+
+        fn every_eight_puzzle(check: &mut dyn FnMut(Puzzle)) {
+            permutations(&mut (0..9).collect::<Vec<usize>>(), 0, &mut |data| {
+                check(Puzzle {
+                    data: data.to_vec(),
+                    width: 3,
+                    height: 3,
+                })
+            });
+        }
+
+        // tries every subset of the tiles already in their goal line
+        fn brute_force_line(goal_positions: &[usize]) -> usize {
+            let len = goal_positions.len();
+            let longest = (0..1usize << len)
+                .filter(|mask| {
+                    let kept: Vec<usize> = (0..len)
+                        .filter(|i| mask & (1 << i) != 0)
+                        .map(|i| goal_positions[i])
+                        .collect();
+                    kept.windows(2).all(|pair| pair[0] < pair[1])
+                })
+                .map(|mask| mask.count_ones() as usize)
+                .max()
+                .unwrap();
+
+            len - longest
+        }
+
+        fn brute_force(puzzle: &Puzzle, goal: &Puzzle) -> usize {
+            let mut conflicts = 0;
+            for line in 0..3 {
+                let mut in_row = vec![];
+                let mut in_column = vec![];
+                for i in 0..3 {
+                    let value = puzzle.get_value(i, line);
+                    let (goal_x, goal_y) = goal.get_position(value);
+                    if value != 0 && goal_y == line {
+                        in_row.push(goal_x);
+                    }
+                    let value = puzzle.get_value(line, i);
+                    let (goal_x, goal_y) = goal.get_position(value);
+                    if value != 0 && goal_x == line {
+                        in_column.push(goal_y);
+                    }
+                }
+                conflicts += brute_force_line(&in_row) + brute_force_line(&in_column);
+            }
 
-        #[test]
-        fn return_true() {
-            let size = 3;
-            let data = vec![3, 2, 1, 8, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let y = 0;
-            let value = 1;
-            let in_correct_column =
-                check_in_correct_row(Puzzle::get_final_state(puzzle.size), value, y);
-
-            assert!(in_correct_column);
-        }
-
-        #[test]
-        fn return_false() {
-            let size = 3;
-            let data = vec![8, 2, 3, 1, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let y = 1;
-            let value = 1;
-            let in_correct_column =
-                check_in_correct_row(Puzzle::get_final_state(puzzle.size), value, y);
-
-            assert!(!in_correct_column);
-        }
-    }
-
-    mod check_column_conflict {
-        use super::super::*;
-
-        #[test]
-        fn with_conflict() {
-            let size = 3;
-            let data = vec![8, 2, 3, 1, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let number_list: Vec<usize> = (0..(size * size)).collect();
-            let value = 1;
-            let x = 0;
-            let heuristic = check_column_conflict(
-                puzzle,
-                Puzzle::get_final_state(size),
-                value,
-                x,
-                &number_list,
-            );
-
-            assert_eq!(heuristic, 1);
-        }
-
-        #[test]
-        fn with_no_conflict() {
-            let size = 3;
-            let data = vec![2, 8, 3, 1, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let number_list: Vec<usize> = (0..(size * size)).collect();
-            let value = 1;
-            let x = 0;
-            let heuristic = check_column_conflict(
-                puzzle,
-                Puzzle::get_final_state(size),
-                value,
-                x,
-                &number_list,
-            );
-
-            assert_eq!(heuristic, 0);
+            conflicts
         }
-    }
-
-    mod check_row_conflict {
-        use super::super::*;
 
         #[test]
-        fn with_conflict() {
-            let size = 3;
-            let data = vec![3, 2, 1, 8, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let number_list: Vec<usize> = (0..(size * size)).collect();
-            let value = 1;
-            let y = 0;
-            let heuristic = check_row_conflict(
-                puzzle,
-                Puzzle::get_final_state(size),
-                value,
-                y,
-                &number_list,
-            );
-
-            assert_eq!(heuristic, 1);
+        fn same_as_brute_force() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let linear_conflict = LinearConflict::new(&goal);
+            every_eight_puzzle(&mut |puzzle| {
+                assert_eq!(
+                    linear_conflict.evaluate(&puzzle),
+                    brute_force(&puzzle, &goal),
+                    "{}",
+                    puzzle
+                );
+            });
         }
 
         #[test]
-        fn with_no_conflict() {
-            let size = 3;
-            let data = vec![8, 1, 3, 2, 0, 4, 7, 6, 5];
-            let puzzle = Puzzle { data, size };
-            let number_list: Vec<usize> = (0..(size * size)).collect();
-            let value = 1;
-            let y = 0;
-            let heuristic = check_row_conflict(
-                puzzle,
-                Puzzle::get_final_state(size),
-                value,
-                y,
-                &number_list,
-            );
-
-            assert_eq!(heuristic, 0);
+        fn update_same_as_new() {
+            let linear_conflict = LinearConflict::new(&Puzzle::get_final_state(3, 3));
+            every_eight_puzzle(&mut |puzzle| {
+                let state = State::from(&puzzle);
+                let score = linear_conflict.evaluate_state(&state);
+                for (movement, child) in state.next_moves() {
+                    assert_eq!(
+                        linear_conflict.update(score, &child, movement),
+                        Some(linear_conflict.evaluate_state(&child)),
+                        "{}{}",
+                        state,
+                        child
+                    );
+                }
+            });
         }
     }
 
-    mod get_possible_values_in_row {
-        use super::super::*;
-
-        #[test]
-        fn row_distance_of_one_with_incremental_range() {
-            let final_puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let puzzle = Puzzle {
-                data: vec![2, 1, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-
-            let value = 1;
-            let possible_values = get_possible_values_in_row(puzzle, final_puzzle, value, 0);
-            let wanted_values = vec![2];
-
-            assert_eq!(possible_values, wanted_values);
+    mod update {
+        use super::permutations;
+        use crate::goal::Goal;
+        use crate::heuristic::*;
+        use crate::state::State;
+
+        // every move from every arrangement of the board
+        fn same_as_full_evaluation<H: FromGoal>() {
+            for (width, height) in [(3, 2), (2, 3), (4, 2)] {
+                for goal in [Goal::Snail, Goal::RowMajor] {
+                    let heuristic = H::new(&goal.state(width, height).unwrap());
+                    let mut data: Vec<usize> = (0..width * height).collect();
+                    permutations(&mut data, 0, &mut |data| {
+                        let state = State::from(&Puzzle {
+                            data: data.to_vec(),
+                            width,
+                            height,
+                        });
+                        let score = heuristic.evaluate_state(&state);
+                        for (movement, child) in state.next_moves() {
+                            assert_eq!(
+                                heuristic.update(score, &child, movement),
+                                Some(heuristic.evaluate_state(&child)),
+                                "{}{}",
+                                state,
+                                child
+                            );
+                        }
+                    });
+                }
+            }
         }
 
         #[test]
-        fn row_distance_of_one_with_decremental_range() {
-            let final_puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let puzzle = Puzzle {
-                data: vec![1, 3, 2, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let value = 3;
-            let possible_values = get_possible_values_in_row(puzzle, final_puzzle, value, 0);
-            let wanted_values = vec![2];
-
-            assert_eq!(possible_values, wanted_values);
+        fn hamming() {
+            same_as_full_evaluation::<Hamming>();
         }
 
         #[test]
-        fn row_distance_of_two_with_incremental_range() {
-            let final_puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let puzzle = Puzzle {
-                data: vec![2, 3, 1, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-
-            let value = 1;
-            let possible_values = get_possible_values_in_row(puzzle, final_puzzle, value, 0);
-            let wanted_values = vec![3, 2];
-
-            assert_eq!(possible_values, wanted_values);
+        fn manhattan() {
+            same_as_full_evaluation::<Manhattan>();
         }
 
         #[test]
-        fn row_distance_of_two_with_decremental_range() {
-            let final_puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let puzzle = Puzzle {
-                data: vec![3, 1, 2, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let value = 3;
-            let possible_values = get_possible_values_in_row(puzzle, final_puzzle, value, 0);
-            let wanted_values = vec![1, 2];
-
-            assert_eq!(possible_values, wanted_values);
+        fn linear_conflict() {
+            same_as_full_evaluation::<LinearConflict>();
         }
 
         #[test]
-        fn row_distance_of_zero() {
-            let final_puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-
-            let value = 1;
-            let possible_values = get_possible_values_in_row(puzzle, final_puzzle, value, 0);
-            let wanted_values = vec![];
-
-            assert_eq!(possible_values, wanted_values);
+        fn manhattan_linear_conflict() {
+            same_as_full_evaluation::<ManhattanLinearConflict>();
         }
     }
 
-    mod get_possible_values_in_column {
+    mod manhattan_linear_conflict {
         use super::super::*;
 
         #[test]
-        fn column_distance_of_one_with_incremental_range() {
-            let final_puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let puzzle = Puzzle {
-                data: vec![8, 2, 3, 1, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let value = 1;
-            let possible_values = get_possible_values_in_column(puzzle, final_puzzle, value, 0);
-            let wanted_values = vec![8];
-
-            assert_eq!(possible_values, wanted_values);
-        }
-
-        #[test]
-        fn column_distance_of_one_with_decremental_range() {
-            let final_puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let puzzle = Puzzle {
-                data: vec![1, 2, 3, 7, 0, 4, 8, 6, 5],
-                size: 3,
-            };
-            let value = 7;
-            let possible_values = get_possible_values_in_column(puzzle, final_puzzle, value, 0);
-            let wanted_values = vec![8];
-
-            assert_eq!(possible_values, wanted_values);
-        }
-
-        #[test]
-        fn column_distance_of_two_with_incremental_range() {
-            let final_puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let puzzle = Puzzle {
-                data: vec![8, 2, 3, 7, 0, 4, 1, 6, 5],
-                size: 3,
-            };
-            let value = 1;
-            let possible_values = get_possible_values_in_column(puzzle, final_puzzle, value, 0);
-            let wanted_values = vec![7, 8];
-
-            assert_eq!(possible_values, wanted_values);
-        }
-
-        #[test]
-        fn column_distance_of_two_with_decremental_range() {
-            let final_puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
-            let puzzle = Puzzle {
-                data: vec![7, 2, 3, 1, 0, 4, 8, 6, 5],
-                size: 3,
-            };
-            let value = 7;
-            let possible_values = get_possible_values_in_column(puzzle, final_puzzle, value, 0);
-            let wanted_values = vec![1, 8];
-
-            assert_eq!(possible_values, wanted_values);
-        }
-
-        #[test]
-        fn column_distance_of_zero() {
-            let final_puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
-            };
+        fn example_case() {
+            let size = 3;
+            let data = vec![1, 2, 3, 7, 4, 0, 8, 5, 6];
             let puzzle = Puzzle {
-                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
+                data,
+                width: size,
+                height: size,
             };
-            let value = 1;
-            let possible_values = get_possible_values_in_column(puzzle, final_puzzle, value, 0);
-            let wanted_values = vec![];
+            let heuristic = ManhattanLinearConflict::new(&Puzzle::get_final_state(size, size))
+                .evaluate(&puzzle);
 
-            assert_eq!(possible_values, wanted_values);
+            assert_eq!(heuristic, 9);
         }
     }
 
-    mod manhattan_linear_conflict_heuristic {
-        use super::super::*;
+    mod metadata {
+        use crate::heuristic::*;
 
         #[test]
-        fn example_case() {
-            let size = 3;
-            let data = vec![1, 2, 3, 7, 4, 0, 8, 5, 6];
-            let puzzle = Puzzle { data, size };
-            let heuristic =
-                manhattan_linear_conflict_heuristic(puzzle, Puzzle::get_final_state(size));
-
-            assert_eq!(heuristic, 9);
+        fn names_match_the_options() {
+            let goal = Puzzle::get_final_state(3, 3);
+
+            assert_eq!(Hamming::new(&goal).name(), "hamming");
+            assert_eq!(Manhattan::new(&goal).name(), "manhattan");
+            assert_eq!(LinearConflict::new(&goal).name(), "linear");
+            assert_eq!(ManhattanLinearConflict::new(&goal).name(), "m2l");
+            assert!(ManhattanLinearConflict::new(&goal).is_admissible());
+            assert_eq!(*Manhattan::new(&goal).goal(), goal);
         }
     }
 }
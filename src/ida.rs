@@ -0,0 +1,257 @@
+use crate::heuristic::Heuristic;
+use crate::node::Node;
+use crate::puzzle::Puzzle;
+use crate::settings::{weighted, Settings};
+use crate::solution::{Progress, Solution, SolveError, Statistics, Stop};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+enum SearchResult {
+    Found,
+    Exceeded(usize),
+    Exhausted,
+    Stopped,
+}
+
+pub struct Ida<H: Heuristic> {
+    pub path: Vec<Node>,
+    pub final_node: Node,
+    pub heuristic: H,
+    // f = g + weight * h, the path found is at most weight times the shortest one
+    pub weight: f64,
+    pub thresholds: Vec<usize>,
+    pub expanded: usize,
+    pub max_states: usize,
+    pub progress: Progress,
+    // for the timeout and cancellation, counted from start_time
+    pub settings: Settings,
+    pub start_time: SystemTime,
+    pub stopped: Option<Stop>,
+}
+
+impl<H: Heuristic> Ida<H> {
+    pub fn new(state: Puzzle, heuristic: H, weight: f64) -> Ida<H> {
+        let final_node = Node::get_final_node(heuristic.goal());
+        let mut start_node = Node::new_starting_node(state);
+        start_node.h_score = heuristic.evaluate_state(&start_node.state);
+        start_node.f_score = weighted(0, start_node.h_score, weight);
+
+        Ida {
+            path: vec![start_node],
+            final_node,
+            heuristic,
+            weight,
+            thresholds: vec![],
+            expanded: 0,
+            max_states: 1,
+            progress: Progress::default(),
+            settings: Settings::default(),
+            start_time: SystemTime::now(),
+            stopped: None,
+        }
+    }
+
+    fn is_going_back(&self, node: &Node) -> bool {
+        // only the grandparent is checked, longer cycles are cut by the threshold
+        self.path.len() >= 2 && self.path[self.path.len() - 2].state == node.state
+    }
+
+    fn search(&mut self, threshold: usize) -> SearchResult {
+        let curr_node = self.path.last().unwrap().clone();
+
+        if curr_node.f_score > threshold {
+            return SearchResult::Exceeded(curr_node.f_score);
+        }
+        if curr_node.state == self.final_node.state {
+            return SearchResult::Found;
+        }
+
+        self.stopped = self.settings.stop(self.start_time);
+        if self.stopped.is_some() {
+            return SearchResult::Stopped;
+        }
+        self.expanded += 1;
+        self.progress.record(curr_node.distance, curr_node.h_score);
+        let mut next_nodes = Node::calculate_next_nodes(Rc::new(curr_node), &self.heuristic);
+        for child in next_nodes.iter_mut() {
+            child.f_score = weighted(child.distance, child.h_score, self.weight);
+        }
+        next_nodes.sort_by(|a, b| b.cmp(a));
+
+        let mut next_threshold = None;
+        for child in next_nodes {
+            if self.is_going_back(&child) {
+                continue;
+            }
+
+            self.path.push(child);
+            if self.max_states < self.path.len() {
+                self.max_states = self.path.len();
+            }
+            match self.search(threshold) {
+                SearchResult::Found => return SearchResult::Found,
+                SearchResult::Stopped => return SearchResult::Stopped,
+                SearchResult::Exceeded(f_score) => {
+                    next_threshold = Some(next_threshold.map_or(f_score, |t: usize| t.min(f_score)))
+                }
+                SearchResult::Exhausted => {}
+            }
+            self.path.pop();
+        }
+
+        match next_threshold {
+            Some(f_score) => SearchResult::Exceeded(f_score),
+            None => SearchResult::Exhausted,
+        }
+    }
+
+    pub fn solve(&mut self) -> bool {
+        let mut threshold = self.path[0].f_score;
+
+        loop {
+            self.thresholds.push(threshold);
+            match self.search(threshold) {
+                SearchResult::Found => return true,
+                SearchResult::Exceeded(f_score) => threshold = f_score,
+                SearchResult::Exhausted | SearchResult::Stopped => return false,
+            }
+        }
+    }
+
+    /*
+     ** no open or closed list here, they stand for the current path and the
+     ** expanded states, printed as such since thresholds is never empty
+     */
+    pub fn statistics(&self, start_time: SystemTime) -> Statistics {
+        Statistics {
+            time_elapsed: start_time.elapsed().unwrap(),
+            open_states: self.path.len(),
+            closed_states: self.expanded,
+            total_states: self.expanded + self.path.len(),
+            max_states: self.max_states,
+            thresholds: self.thresholds.clone(),
+            progress: self.progress,
+            stopped: self.stopped,
+            ..Statistics::default()
+        }
+    }
+
+    pub fn ida_star(
+        state: Puzzle,
+        heuristic: H,
+        settings: &Settings,
+    ) -> Result<Solution, SolveError> {
+        let mut ida = Ida::new(state, heuristic, settings.weight.unwrap_or(1.0));
+        ida.settings = settings.clone();
+
+        if !ida.solve() {
+            return Err(match ida.stopped {
+                Some(stop) => SolveError::Stopped(stop, Box::new(ida.statistics(ida.start_time))),
+                None => SolveError::NotFound,
+            });
+        }
+
+        Ok(Solution::new(
+            ida.path.last().unwrap().retrieve_path(),
+            ida.statistics(ida.start_time),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod ida_tests {
+    mod solve {
+        use crate::heuristic::*;
+        use crate::ida::*;
+
+        #[test]
+        fn already_solved() {
+            let mut ida = Ida::new(
+                Puzzle::get_final_state(3, 3),
+                ManhattanLinearConflict::new(&Puzzle::get_final_state(3, 3)),
+                1.0,
+            );
+
+            assert!(ida.solve());
+            assert_eq!(ida.path.len(), 1);
+            assert_eq!(ida.thresholds, vec![0]);
+        }
+
+        #[test]
+        fn two_moves() {
+            let mut ida = Ida::new(
+                Puzzle {
+                    data: vec![0, 2, 3, 1, 8, 4, 7, 6, 5],
+                    width: 3,
+                    height: 3,
+                },
+                ManhattanLinearConflict::new(&Puzzle::get_final_state(3, 3)),
+                1.0,
+            );
+
+            assert!(ida.solve());
+            assert_eq!(ida.path.len(), 3);
+            assert_eq!(ida.path.last().unwrap().distance, 2);
+            assert_eq!(ida.path.last().unwrap().state, ida.final_node.state);
+        }
+
+        #[test]
+        fn thresholds_are_increasing() {
+            let mut ida = Ida::new(
+                Puzzle {
+                    data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                    width: 3,
+                    height: 3,
+                },
+                Manhattan::new(&Puzzle::get_final_state(3, 3)),
+                1.0,
+            );
+
+            assert!(ida.solve());
+            assert_eq!(ida.path.last().unwrap().distance, 15);
+            assert!(ida.thresholds.windows(2).all(|t| t[0] < t[1]));
+            assert_eq!(*ida.thresholds.last().unwrap(), 15);
+        }
+
+        #[test]
+        fn weighted() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let start = Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            };
+            let settings = Settings {
+                weight: Some(5.0),
+                ..Settings::default()
+            };
+            let optimal =
+                Ida::ida_star(start.clone(), Manhattan::new(&goal), &Settings::default()).unwrap();
+            let solution = Ida::ida_star(start, Manhattan::new(&goal), &settings).unwrap();
+
+            assert!(solution.len() <= 5 * optimal.len());
+            assert!(solution.statistics.closed_states < optimal.statistics.closed_states);
+            assert_eq!(*solution.states.last().unwrap(), goal);
+        }
+
+        #[test]
+        fn cancelled() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let settings = Settings::default();
+            settings.cancel.cancel();
+            let start = Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            };
+
+            match Ida::ida_star(start, Manhattan::new(&goal), &settings) {
+                Err(SolveError::Stopped(Stop::Cancelled, statistics)) => {
+                    assert_eq!(statistics.closed_states, 0);
+                    assert_eq!(statistics.thresholds.len(), 1);
+                }
+                result => panic!("{:?}", result),
+            }
+        }
+    }
+}
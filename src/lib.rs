@@ -0,0 +1,377 @@
+pub mod ara;
+pub mod bidirectional;
+pub mod direction;
+pub mod expression;
+pub mod generator;
+pub mod goal;
+pub mod graph;
+pub mod hda;
+pub mod heuristic;
+pub mod ida;
+pub mod node;
+pub mod options;
+pub mod parser;
+pub mod pattern_database;
+pub mod puzzle;
+pub mod report;
+pub mod settings;
+pub mod sma;
+pub mod solution;
+pub mod state;
+pub mod verify;
+pub mod walking_distance;
+
+use crate::ara::Ara;
+use crate::bidirectional::Bidirectional;
+use crate::expression::{Combination, Expression};
+use crate::graph::Graph;
+use crate::hda::Hda;
+use crate::heuristic::{
+    Blind, FromGoal, Hamming, Heuristic, LinearConflict, Manhattan, ManhattanLinearConflict,
+};
+use crate::ida::Ida;
+use crate::options::AlgorithmValues;
+use crate::options::GoalValues;
+use crate::options::HeuristicValues;
+use crate::pattern_database::PatternDatabase;
+use crate::settings::Settings;
+use crate::sma::Sma;
+use crate::walking_distance::WalkingDistance;
+
+pub use crate::goal::Goal;
+pub use crate::puzzle::Puzzle;
+pub use crate::solution::{Improvement, Solution, SolveError, Statistics};
+
+pub type Algorithm<H> = fn(Puzzle, H, &Settings) -> Result<Solution, SolveError>;
+
+pub fn get_goal(goal_value: &GoalValues) -> Goal {
+    match goal_value {
+        GoalValues::Snail => Goal::Snail,
+        GoalValues::RowMajor => Goal::RowMajor,
+        GoalValues::BlankFirst => Goal::BlankFirst,
+    }
+}
+
+pub fn get_algorithm<H: Heuristic>(algorithm_value: &AlgorithmValues) -> Algorithm<H> {
+    match algorithm_value {
+        AlgorithmValues::Greedy => Graph::greedy,
+        AlgorithmValues::Astar => Graph::a_star,
+        AlgorithmValues::Ida => Ida::ida_star,
+        AlgorithmValues::Ara => Ara::ara_star,
+        AlgorithmValues::Bidirectional => Bidirectional::mm,
+        AlgorithmValues::Bfs => Graph::breadth_first,
+        AlgorithmValues::UniformCost => Graph::uniform_cost,
+        AlgorithmValues::Hda => Hda::hda_star,
+        AlgorithmValues::Sma => Sma::sma_star,
+    }
+}
+
+/*
+ ** the heuristic is only set up once the puzzle is known to be solvable, a pattern
+ ** database may take a while to build
+ */
+fn run<H: FromGoal>(
+    puzzle: Puzzle,
+    goal_puzzle: &Puzzle,
+    algorithm: &AlgorithmValues,
+    settings: &Settings,
+) -> Result<Solution, SolveError> {
+    get_algorithm(algorithm)(puzzle, H::new(goal_puzzle), settings)
+}
+
+pub fn solve(
+    puzzle: Puzzle,
+    goal: &Goal,
+    algorithm: &AlgorithmValues,
+    heuristic: &Expression,
+    settings: &Settings,
+) -> Result<Solution, SolveError> {
+    let goal_puzzle = goal
+        .state(puzzle.width, puzzle.height)
+        .ok_or(SolveError::WrongGoalSize)?;
+    if !Puzzle::is_solvable(puzzle.clone(), &goal_puzzle) {
+        return Err(SolveError::Unsolvable);
+    }
+
+    // no point in building a heuristic a blind search won't look at
+    if let AlgorithmValues::Bfs | AlgorithmValues::UniformCost = algorithm {
+        return run::<Blind>(puzzle, &goal_puzzle, algorithm, settings);
+    }
+    // a single heuristic keeps its own type, and its incremental update
+    let value = match heuristic {
+        Expression::Heuristic(value) => value,
+        expression => {
+            let combination = Combination::new(expression, &goal_puzzle);
+            return get_algorithm(algorithm)(puzzle, combination, settings);
+        }
+    };
+    match value {
+        HeuristicValues::Hamming => run::<Hamming>(puzzle, &goal_puzzle, algorithm, settings),
+        HeuristicValues::Manhattan => run::<Manhattan>(puzzle, &goal_puzzle, algorithm, settings),
+        HeuristicValues::Linear => run::<LinearConflict>(puzzle, &goal_puzzle, algorithm, settings),
+        HeuristicValues::M2L => {
+            run::<ManhattanLinearConflict>(puzzle, &goal_puzzle, algorithm, settings)
+        }
+        HeuristicValues::Pdb => run::<PatternDatabase>(puzzle, &goal_puzzle, algorithm, settings),
+        HeuristicValues::Walking => {
+            run::<WalkingDistance>(puzzle, &goal_puzzle, algorithm, settings)
+        }
+    }
+}
+
+/*
+ ** with a heuristic of your own, already bound to the goal
+ */
+pub fn solve_with_heuristic<H: Heuristic>(
+    puzzle: Puzzle,
+    heuristic: H,
+    algorithm: &AlgorithmValues,
+    settings: &Settings,
+) -> Result<Solution, SolveError> {
+    let goal_puzzle = heuristic.goal();
+    if (goal_puzzle.width, goal_puzzle.height) != (puzzle.width, puzzle.height) {
+        return Err(SolveError::WrongGoalSize);
+    }
+    if !Puzzle::is_solvable(puzzle.clone(), goal_puzzle) {
+        return Err(SolveError::Unsolvable);
+    }
+
+    get_algorithm(algorithm)(puzzle, heuristic, settings)
+}
+
+#[cfg(test)]
+mod lib_tests {
+    mod solve {
+        use crate::generator::{self, Method};
+        use crate::options::{AlgorithmValues, HeuristicValues};
+        use crate::*;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        #[test]
+        fn every_algorithm_finds_a_path() {
+            let puzzle = Puzzle {
+                data: vec![0, 2, 3, 1, 8, 4, 7, 6, 5],
+                width: 3,
+                height: 3,
+            };
+
+            for algorithm in &[
+                AlgorithmValues::Greedy,
+                AlgorithmValues::Astar,
+                AlgorithmValues::Ida,
+                AlgorithmValues::Ara,
+                AlgorithmValues::Bidirectional,
+                AlgorithmValues::Bfs,
+                AlgorithmValues::UniformCost,
+                AlgorithmValues::Hda,
+                AlgorithmValues::Sma,
+            ] {
+                let solution = solve(
+                    puzzle.clone(),
+                    &Goal::Snail,
+                    algorithm,
+                    &HeuristicValues::Manhattan.into(),
+                    &Settings::default(),
+                )
+                .unwrap();
+
+                assert_eq!(solution.len(), 2);
+                assert_eq!(solution.states[0], puzzle);
+                assert_eq!(
+                    *solution.states.last().unwrap(),
+                    Puzzle::get_final_state(3, 3)
+                );
+            }
+        }
+
+        // breadth-first search as the ground truth for every admissible heuristic
+        #[test]
+        fn every_heuristic_finds_the_shortest_path() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let mut rng = StdRng::seed_from_u64(42);
+            for moves in [6, 12, 18, 24, 30, 36] {
+                let puzzle = generator::generate(&mut rng, &goal, Method::Walk(moves), true);
+                let shortest = solve(
+                    puzzle.clone(),
+                    &Goal::Snail,
+                    &AlgorithmValues::Bfs,
+                    &HeuristicValues::Manhattan.into(),
+                    &Settings::default(),
+                )
+                .unwrap()
+                .len();
+
+                // pdb would save its database to disk, its own tests compare it with
+                // the true distances
+                for heuristic in HeuristicValues::variants()
+                    .iter()
+                    .map(|heuristic| heuristic.parse::<HeuristicValues>().unwrap())
+                    .filter(|&heuristic| heuristic != HeuristicValues::Pdb)
+                {
+                    let solution = solve(
+                        puzzle.clone(),
+                        &Goal::Snail,
+                        &AlgorithmValues::Astar,
+                        &heuristic.into(),
+                        &Settings::default(),
+                    )
+                    .unwrap();
+
+                    assert_eq!(solution.len(), shortest, "{} on {}", heuristic, puzzle);
+                }
+            }
+        }
+
+        #[test]
+        fn unsolvable() {
+            let puzzle = Puzzle {
+                data: vec![0, 1, 2, 3, 4, 5, 6, 7, 8],
+                width: 3,
+                height: 3,
+            };
+
+            assert_eq!(
+                solve(
+                    puzzle,
+                    &Goal::Snail,
+                    &AlgorithmValues::Astar,
+                    &HeuristicValues::Manhattan.into(),
+                    &Settings::default(),
+                )
+                .unwrap_err(),
+                SolveError::Unsolvable
+            );
+        }
+
+        #[test]
+        fn row_major_goal() {
+            let puzzle = Puzzle {
+                data: vec![1, 2, 3, 4, 5, 6, 0, 7, 8],
+                width: 3,
+                height: 3,
+            };
+            let solution = solve(
+                puzzle,
+                &Goal::RowMajor,
+                &AlgorithmValues::Astar,
+                &HeuristicValues::M2L.into(),
+                &Settings::default(),
+            )
+            .unwrap();
+
+            assert_eq!(solution.len(), 2);
+            assert_eq!(
+                *solution.states.last().unwrap(),
+                Goal::RowMajor.state(3, 3).unwrap()
+            );
+        }
+
+        #[test]
+        fn combined_heuristic() {
+            let puzzle = Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            };
+            let heuristic = "max(hamming,manhattan)".parse().unwrap();
+            let solution = solve(
+                puzzle,
+                &Goal::Snail,
+                &AlgorithmValues::Ida,
+                &heuristic,
+                &Settings::default(),
+            )
+            .unwrap();
+
+            assert_eq!(solution.len(), 15);
+        }
+
+        #[test]
+        fn more_than_256_tiles() {
+            let goal = Puzzle::get_final_state(17, 17);
+            let mut rng = StdRng::seed_from_u64(17);
+            let puzzle = generator::generate(&mut rng, &goal, Method::Walk(20), true);
+            let solution = solve(
+                puzzle.clone(),
+                &Goal::Snail,
+                &AlgorithmValues::Astar,
+                &HeuristicValues::Manhattan.into(),
+                &Settings::default(),
+            )
+            .unwrap();
+
+            assert!(solution.len() <= 20);
+            assert_eq!(solution.states[0], puzzle);
+            assert_eq!(*solution.states.last().unwrap(), goal);
+        }
+
+        #[test]
+        fn wrong_goal_size() {
+            let goal = Goal::Custom(Puzzle::get_final_state(4, 4));
+
+            assert_eq!(
+                solve(
+                    Puzzle::get_final_state(3, 3),
+                    &goal,
+                    &AlgorithmValues::Astar,
+                    &HeuristicValues::Manhattan.into(),
+                    &Settings::default(),
+                )
+                .unwrap_err(),
+                SolveError::WrongGoalSize
+            );
+        }
+
+        #[test]
+        fn custom_heuristic() {
+            struct Zero(Puzzle);
+
+            impl FromGoal for Zero {
+                fn new(goal: &Puzzle) -> Zero {
+                    Zero(goal.clone())
+                }
+            }
+
+            impl Heuristic for Zero {
+                fn name(&self) -> &'static str {
+                    "zero"
+                }
+
+                fn goal(&self) -> &Puzzle {
+                    &self.0
+                }
+
+                fn evaluate(&self, _puzzle: &Puzzle) -> usize {
+                    0
+                }
+            }
+
+            let puzzle = Puzzle {
+                data: vec![0, 2, 3, 1, 8, 4, 7, 6, 5],
+                width: 3,
+                height: 3,
+            };
+            let goal = Puzzle::get_final_state(3, 3);
+            let solution = solve_with_heuristic(
+                puzzle,
+                Zero::new(&goal),
+                &AlgorithmValues::Astar,
+                &Settings::default(),
+            )
+            .unwrap();
+
+            assert_eq!(solution.len(), 2);
+            assert_eq!(
+                solve_with_heuristic(
+                    goal.clone(),
+                    Zero::new(&Puzzle::get_final_state(4, 4)),
+                    &AlgorithmValues::Ida,
+                    &Settings::default(),
+                )
+                .unwrap_err(),
+                SolveError::WrongGoalSize
+            );
+        }
+    }
+}
@@ -1,67 +1,306 @@
+use n_puzzle::expression::{Combination, Expression};
+use n_puzzle::generator;
+use n_puzzle::generator::Method;
+use n_puzzle::heuristic::Heuristic;
+use n_puzzle::options::{
+    AlgorithmValues, BoardSize, Command, FormatValues, GoalValues, Opt, OutputValues,
+};
+use n_puzzle::parser;
+use n_puzzle::parser::ParseError;
+use n_puzzle::report;
+use n_puzzle::settings::{CancelToken, Settings};
+use n_puzzle::verify;
+use n_puzzle::{Goal, Puzzle, SolveError};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
 use std::fs::File;
+use std::io;
 use std::io::BufRead;
 use std::io::BufReader;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process;
 use structopt::StructOpt;
 
-mod graph;
-mod heuristic;
-mod node;
-mod options;
-mod parser;
 mod print_result;
-mod puzzle;
 
-use crate::graph::Graph;
-use crate::options::AlgorithmValues;
-use crate::options::HeuristicValues;
-use options::Opt;
-use puzzle::Puzzle;
+fn file_to_vec(filename: &PathBuf) -> io::Result<Vec<String>> {
+    let file = File::open(filename)?;
+    let br_file = BufReader::new(file);
 
-fn open_file(filename: PathBuf) -> File {
-    File::open(filename).expect("Could not open file")
+    br_file.lines().collect()
 }
 
-fn file_to_vec(filename: PathBuf) -> Vec<String> {
-    let file = open_file(filename);
-    let br_file = BufReader::new(file);
+fn read_file<T, F>(filename: PathBuf, parse: F) -> T
+where
+    F: FnOnce(Vec<String>) -> Result<T, ParseError>,
+{
+    let lines = match file_to_vec(&filename) {
+        Ok(lines) => lines,
+        Err(error) => {
+            eprintln!("error: could not read {:?} : {}", filename, error);
+            process::exit(1);
+        }
+    };
+
+    match parse(lines.clone()) {
+        Ok(value) => value,
+        Err(error) => {
+            print_result::print_parse_error(&filename, &lines, &error);
+            process::exit(1);
+        }
+    }
+}
 
-    let lines: Vec<String> = br_file.lines().collect::<Result<_, _>>().unwrap();
+/*
+ ** the first Ctrl-C stops the search and still prints what it got to, a second one
+ ** quits right away
+ */
+fn cancel_on_interrupt() -> CancelToken {
+    let cancel = CancelToken::new();
+    let handler = cancel.clone();
+    if let Err(error) = ctrlc::set_handler(move || {
+        if handler.is_cancelled() {
+            process::exit(130);
+        }
+        handler.cancel();
+    }) {
+        eprintln!(
+            "warning: Ctrl-C will not stop the search cleanly : {}",
+            error
+        );
+    }
 
-    lines
+    cancel
 }
 
-pub fn get_heuristic(heuristic_value: &HeuristicValues) -> fn(Puzzle, Puzzle) -> usize {
-    match heuristic_value {
-        HeuristicValues::Hamming => heuristic::hamming_distance,
-        HeuristicValues::Manhattan => heuristic::manhattan_distance,
-        HeuristicValues::Linear => heuristic::linear_conflict,
-        HeuristicValues::M2L => heuristic::manhattan_linear_conflict_heuristic,
+/*
+ ** replays the moves, then compares their number with an optimal solve if asked
+ */
+fn verify(
+    opt: &Opt,
+    goal: &Goal,
+    file: &Path,
+    moves: Option<&String>,
+    moves_file: Option<&Path>,
+    optimal: bool,
+) {
+    let puzzle = read_file(file.to_path_buf(), |lines| parser::parse(lines, goal));
+    let moves = match (moves, moves_file) {
+        (Some(moves), _) => moves.clone(),
+        (None, Some(moves_file)) => fs::read_to_string(moves_file).unwrap_or_else(|error| {
+            eprintln!("error: could not read {:?} : {}", moves_file, error);
+            process::exit(1);
+        }),
+        (None, None) => panic!("Something went wrong with the parameters !"),
+    };
+    let goal_puzzle = goal
+        .state(puzzle.width, puzzle.height)
+        .expect("the goal size was checked by the parser");
+    println!("{}", puzzle);
+
+    let replay = verify::replay(&puzzle, &goal_puzzle, &moves);
+    print_result::print_replay(&replay);
+    if !replay.reaches_goal {
+        process::exit(1);
+    }
+    if !optimal {
+        return;
+    }
+
+    let admissible = match &opt.heuristic {
+        Expression::Heuristic(_) => true,
+        expression => Combination::new(expression, &goal_puzzle).is_admissible(),
+    };
+    if !admissible {
+        eprintln!(
+            "warning: {} may overestimate, the shortest length found may not be the shortest one",
+            opt.heuristic
+        );
+    }
+    let settings = Settings {
+        timeout: opt.timeout,
+        cancel: cancel_on_interrupt(),
+        ..Settings::default()
+    };
+    match n_puzzle::solve(
+        puzzle,
+        goal,
+        &AlgorithmValues::Astar,
+        &opt.heuristic,
+        &settings,
+    ) {
+        Ok(solution) => {
+            print_result::print_optimality(replay.moves, solution.len());
+            if replay.moves > solution.len() {
+                process::exit(1);
+            }
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
     }
 }
 
-pub fn get_algorithm(algorithm_value: &AlgorithmValues) -> fn(Puzzle, fn(Puzzle, Puzzle) -> usize) {
-    match algorithm_value {
-        AlgorithmValues::Greedy => Graph::a_star_greedy,
-        AlgorithmValues::Astar => Graph::a_star,
+/*
+ ** the header tells whether the board is solvable, as generator.py did, and how to
+ ** generate it again
+ */
+fn generate(
+    opt: &Opt,
+    goal: &Goal,
+    size: BoardSize,
+    seed: Option<u64>,
+    solvable: Option<bool>,
+    walk: Option<usize>,
+) {
+    let goal_puzzle = goal.state(size.width, size.height).unwrap_or_else(|| {
+        eprintln!("{}", SolveError::WrongGoalSize);
+        process::exit(1);
+    });
+    let seed = seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let solvable = solvable.unwrap_or_else(|| rng.gen());
+    let method = walk.map_or(Method::Uniform, Method::Walk);
+    let puzzle = generator::generate(&mut rng, &goal_puzzle, method, solvable);
+
+    let mut command = format!("n-puzzle generate {} --seed {}", size, seed);
+    if let Some(walk) = walk {
+        command += &format!(" --walk {}", walk);
     }
+    command += if solvable {
+        " --solvable"
+    } else {
+        " --unsolvable"
+    };
+    match (&opt.goal, &opt.goal_file) {
+        (_, Some(goal_file)) => command += &format!(" --goal-file {}", goal_file.display()),
+        (Some(goal), None) => command += &format!(" --goal {}", goal),
+        (None, None) => {}
+    }
+    let comments = [
+        format!(
+            "This puzzle is {}",
+            if solvable { "solvable" } else { "unsolvable" }
+        ),
+        command,
+    ];
+    print!("{}", generator::to_file(&puzzle, &comments));
 }
 
 fn main() {
     let opt = Opt::from_args();
-    println!("{}", opt);
-    let heuristic = get_heuristic(&opt.heuristic);
-    let algorithm = get_algorithm(&opt.algorithm);
-    if opt.size != None {
-        let puzzle = Puzzle::new(opt.size.unwrap());
-        println!("{}", puzzle);
-        algorithm(puzzle, heuristic);
-    } else if opt.file != None {
-        let filename = opt.file.unwrap();
-        let puzzle = parser::parse(file_to_vec(filename));
-        println!("{}", puzzle);
-        algorithm(puzzle, heuristic);
+    let goal = if let Some(filename) = &opt.goal_file {
+        Goal::Custom(read_file(filename.clone(), parser::parse_goal))
+    } else {
+        n_puzzle::get_goal(opt.goal.as_ref().unwrap_or(&GoalValues::Snail))
+    };
+    match &opt.command {
+        Some(Command::Verify {
+            file,
+            moves,
+            moves_file,
+            optimal,
+        }) => {
+            verify(
+                &opt,
+                &goal,
+                file,
+                moves.as_ref(),
+                moves_file.as_deref(),
+                *optimal,
+            );
+            return;
+        }
+        Some(Command::Generate {
+            size,
+            seed,
+            solvable,
+            unsolvable,
+            walk,
+        }) => {
+            let solvable = match (solvable, unsolvable) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+            generate(&opt, &goal, *size, *seed, solvable, *walk);
+            return;
+        }
+        None => {}
+    }
+    let text = opt.format == FormatValues::Text;
+    if text {
+        println!("{}", opt);
+    }
+    let puzzle = if let Some(size) = opt.size {
+        match goal.state(size.width, size.height) {
+            Some(goal_puzzle) => Puzzle::new(size.width, size.height, &goal_puzzle),
+            None => {
+                eprintln!("{}", n_puzzle::SolveError::WrongGoalSize);
+                process::exit(1);
+            }
+        }
+    } else if let Some(filename) = opt.file.clone() {
+        read_file(filename, |lines| parser::parse(lines, &goal))
     } else {
         panic!("Something went wrong with the parameters !");
+    };
+    if text {
+        println!("{}", puzzle);
+    }
+
+    let settings = Settings {
+        weight: opt.weight,
+        // nothing but the report in json
+        on_improvement: if text {
+            Some(print_result::print_improvement)
+        } else {
+            None
+        },
+        threads: opt.threads,
+        max_states: opt.max_states,
+        max_memory: opt.max_memory,
+        timeout: opt.timeout,
+        cancel: cancel_on_interrupt(),
+    };
+    let result = n_puzzle::solve(
+        puzzle.clone(),
+        &goal,
+        &opt.algorithm,
+        &opt.heuristic,
+        &settings,
+    );
+    if !text {
+        let goal_puzzle = goal
+            .state(puzzle.width, puzzle.height)
+            .expect("the goal size was checked with the puzzle");
+        let report = report::to_json(
+            &puzzle,
+            &goal_puzzle,
+            &opt.algorithm,
+            &opt.heuristic,
+            &result,
+        );
+        println!("{}", report);
+        process::exit(if result.is_ok() { 0 } else { 1 });
+    }
+    match result {
+        Ok(solution) => {
+            match opt.output {
+                OutputValues::Boards => print_result::print_solution(&solution.states),
+                OutputValues::Moves => print_result::print_moves(&solution),
+                OutputValues::Tiles => print_result::print_tiles(&solution),
+            }
+            print_result::print_data(&solution);
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            if let SolveError::Stopped(_, statistics) = &error {
+                print_result::print_statistics(statistics);
+            }
+            process::exit(1);
+        }
     }
 }
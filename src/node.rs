@@ -1,17 +1,30 @@
+use crate::heuristic::Heuristic;
 use crate::puzzle::Puzzle;
+use crate::state::State;
 use std::cmp::Ordering;
 use std::fmt;
+use std::rc::Rc;
 
 #[derive(Clone, Eq)]
 pub struct Node {
-    pub state: Puzzle,
+    pub state: State,
     pub distance: usize,
     pub f_score: usize,
+    // kept apart from f_score, which may weigh it
+    pub h_score: usize,
+    pub parent: Option<Rc<Node>>,
 }
 
 impl Ord for Node {
+    // reversed so that BinaryHeap pops the lowest f_score first,
+    // ties going to the node furthest from the start, then to any fixed order of
+    // the states so that only equal nodes compare equal
     fn cmp(&self, other: &Self) -> Ordering {
-        self.f_score.cmp(&other.f_score)
+        other
+            .f_score
+            .cmp(&self.f_score)
+            .then_with(|| self.distance.cmp(&other.distance))
+            .then_with(|| self.state.cmp(&other.state))
     }
 }
 
@@ -23,7 +36,7 @@ impl PartialOrd for Node {
 
 impl PartialEq for Node {
     fn eq(&self, other: &Self) -> bool {
-        self.f_score == other.f_score && self.state == other.state
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -51,14 +64,18 @@ impl Node {
             state: self.state.clone(),
             distance: self.distance,
             f_score: self.f_score,
+            h_score: self.h_score,
+            parent: None,
         }
     }
 
     pub fn new_starting_node(state: Puzzle) -> Node {
         Node {
-            state: state.clone(),
+            state: State::from(&state),
             distance: 0,
             f_score: 0,
+            h_score: 0,
+            parent: None,
         }
     }
 
@@ -83,42 +100,54 @@ impl Node {
         if y != 0 {
             childs.push(Node::swap_two_positions(puzzle, x, y, x, y - 1));
         }
-        if y != puzzle.size - 1 {
+        if y != puzzle.height - 1 {
             childs.push(Node::swap_two_positions(puzzle, x, y, x, y + 1));
         }
         if x != 0 {
             childs.push(Node::swap_two_positions(puzzle, x, y, x - 1, y));
         }
-        if x != puzzle.size - 1 {
+        if x != puzzle.width - 1 {
             childs.push(Node::swap_two_positions(puzzle, x, y, x + 1, y));
         }
 
         childs
     }
 
-    pub fn calculate_next_nodes(
-        parent: Node,
-        final_node: Node,
-        heuristic: fn(Puzzle, Puzzle) -> usize,
-    ) -> Vec<Node> {
+    pub fn calculate_next_nodes<H: Heuristic>(parent: Rc<Node>, heuristic: &H) -> Vec<Node> {
         let mut childs = Vec::new();
-        let next_states = Node::calculate_next_states(&parent.state);
-        for state in next_states {
+        for (movement, state) in parent.state.next_moves() {
+            let h_score = heuristic.evaluate_move(parent.h_score, &state, movement);
             childs.push(Node {
-                state: state.clone(),
+                state,
                 distance: parent.distance + 1,
-                f_score: parent.distance + 1 + heuristic(state, final_node.clone().state),
+                f_score: parent.distance + 1 + h_score,
+                h_score,
+                parent: Some(Rc::clone(&parent)),
             });
         }
 
         childs
     }
 
-    pub fn get_final_node(size: usize) -> Node {
+    pub fn retrieve_path(&self) -> Vec<Puzzle> {
+        let mut path = vec![Puzzle::from(&self.state)];
+        let mut curr_node = self;
+        while let Some(parent) = &curr_node.parent {
+            path.push(Puzzle::from(&parent.state));
+            curr_node = parent;
+        }
+        path.reverse();
+
+        path
+    }
+
+    pub fn get_final_node(goal_puzzle: &Puzzle) -> Node {
         Node {
-            state: Puzzle::get_final_state(size),
+            state: State::from(goal_puzzle),
             distance: 0,
             f_score: 0,
+            h_score: 0,
+            parent: None,
         }
     }
 }
@@ -133,11 +162,16 @@ mod node_tests {
         fn down_swap() {
             let size = 3;
             let data = vec![0, 1, 2, 3, 4, 5, 6, 8, 7];
-            let puzzle = Puzzle { data, size };
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
             let result_data = vec![3, 1, 2, 0, 4, 5, 6, 8, 7];
             let result_puzzle = Puzzle {
                 data: result_data,
-                size,
+                width: size,
+                height: size,
             };
 
             assert_eq!(Node::swap_two_positions(&puzzle, 0, 0, 0, 1), result_puzzle);
@@ -147,11 +181,16 @@ mod node_tests {
         fn right_swap() {
             let size = 3;
             let data = vec![0, 1, 2, 3, 4, 5, 6, 8, 7];
-            let puzzle = Puzzle { data, size };
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
             let result_data = vec![1, 0, 2, 3, 4, 5, 6, 8, 7];
             let result_puzzle = Puzzle {
                 data: result_data,
-                size,
+                width: size,
+                height: size,
             };
 
             assert_eq!(Node::swap_two_positions(&puzzle, 0, 0, 1, 0), result_puzzle);
@@ -168,17 +207,20 @@ mod node_tests {
 
             let puzzle = Puzzle {
                 data: vec![0, 1, 2, 3, 4, 5, 6, 8, 7],
-                size,
+                width: size,
+                height: size,
             };
 
             let result1 = Puzzle {
                 data: vec![3, 1, 2, 0, 4, 5, 6, 8, 7],
-                size,
+                width: size,
+                height: size,
             };
 
             let result2 = Puzzle {
                 data: vec![1, 0, 2, 3, 4, 5, 6, 8, 7],
-                size,
+                width: size,
+                height: size,
             };
 
             assert_eq!(Node::calculate_next_states(&puzzle), vec![result1, result2]);
@@ -188,26 +230,39 @@ mod node_tests {
     mod partial_eq {
         use super::super::Puzzle;
         use crate::node::Node;
+        use crate::state::State;
 
         #[test]
         fn equals() {
             let len = 0;
             let size = 3;
             let data = vec![0, 1, 2, 3, 4, 5, 6, 8, 7];
-            let puzzle = Puzzle { data, size };
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
             let node = Node {
-                state: puzzle,
+                state: State::from(&puzzle),
                 distance: len,
                 f_score: len,
+                h_score: 0,
+                parent: None,
             };
 
             let len2 = 0;
             let data2 = vec![0, 1, 2, 3, 4, 5, 6, 8, 7];
-            let puzzle2 = Puzzle { data: data2, size };
+            let puzzle2 = Puzzle {
+                data: data2,
+                width: size,
+                height: size,
+            };
             let node2 = Node {
-                state: puzzle2,
+                state: State::from(&puzzle2),
                 distance: len2,
                 f_score: len2,
+                h_score: 0,
+                parent: None,
             };
 
             assert_eq!(node, node2);
@@ -218,22 +273,139 @@ mod node_tests {
             let len = 0;
             let size = 3;
             let data = vec![0, 1, 2, 3, 4, 5, 6, 8, 7];
-            let puzzle = Puzzle { data, size };
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
             let node = Node {
-                state: puzzle,
+                state: State::from(&puzzle),
                 distance: len,
                 f_score: len,
+                h_score: 0,
+                parent: None,
             };
 
             let data2 = vec![0, 1, 2, 3, 4, 5, 6, 7, 8];
-            let puzzle2 = Puzzle { data: data2, size };
+            let puzzle2 = Puzzle {
+                data: data2,
+                width: size,
+                height: size,
+            };
             let node2 = Node {
-                state: puzzle2,
+                state: State::from(&puzzle2),
                 distance: len,
                 f_score: len + 1,
+                h_score: 1,
+                parent: None,
             };
 
             assert_ne!(node, node2);
         }
     }
+
+    mod cmp {
+        use super::super::Puzzle;
+        use crate::node::Node;
+        use crate::state::State;
+        use std::cmp::Ordering;
+
+        fn node(data: Vec<usize>, distance: usize, f_score: usize) -> Node {
+            let puzzle = Puzzle {
+                data,
+                width: 3,
+                height: 3,
+            };
+            Node {
+                state: State::from(&puzzle),
+                distance,
+                f_score,
+                h_score: f_score - distance,
+                parent: None,
+            }
+        }
+
+        #[test]
+        fn equal_only_when_eq() {
+            let nodes = [
+                node(vec![0, 1, 2, 3, 4, 5, 6, 8, 7], 2, 4),
+                node(vec![0, 1, 2, 3, 4, 5, 6, 8, 7], 1, 4),
+                node(vec![1, 0, 2, 3, 4, 5, 6, 8, 7], 2, 4),
+                node(vec![0, 1, 2, 3, 4, 5, 6, 8, 7], 2, 5),
+            ];
+
+            for first in &nodes {
+                for second in &nodes {
+                    assert_eq!(first.cmp(second) == Ordering::Equal, first == second);
+                    assert_eq!(first.cmp(second), second.cmp(first).reverse());
+                }
+            }
+            assert_eq!(nodes[0].cmp(&nodes[0].clone()), Ordering::Equal);
+        }
+
+        #[test]
+        fn lowest_f_then_furthest_first() {
+            let mut heap = std::collections::BinaryHeap::new();
+            heap.push(node(vec![0, 1, 2, 3, 4, 5, 6, 8, 7], 1, 4));
+            heap.push(node(vec![0, 1, 2, 3, 4, 5, 6, 8, 7], 2, 5));
+            heap.push(node(vec![1, 0, 2, 3, 4, 5, 6, 8, 7], 2, 4));
+
+            let order: Vec<(usize, usize)> = std::iter::from_fn(|| heap.pop())
+                .map(|node| (node.f_score, node.distance))
+                .collect();
+            assert_eq!(order, vec![(4, 2), (4, 1), (5, 2)]);
+        }
+    }
+
+    mod retrieve_path {
+        use super::super::Puzzle;
+        use crate::heuristic::{FromGoal, Hamming, Heuristic};
+        use crate::node::Node;
+        use std::rc::Rc;
+
+        #[test]
+        fn starting_node_only() {
+            let puzzle = Puzzle::get_final_state(3, 3);
+            let node = Node::new_starting_node(puzzle.clone());
+
+            assert_eq!(node.retrieve_path(), vec![puzzle]);
+        }
+
+        #[test]
+        fn follows_parents_from_start() {
+            let heuristic = Hamming::new(&Puzzle::get_final_state(3, 3));
+            let mut start = Node::new_starting_node(Puzzle {
+                data: vec![1, 2, 3, 8, 4, 5, 7, 6, 0],
+                width: 3,
+                height: 3,
+            });
+            start.h_score = heuristic.evaluate_state(&start.state);
+            let start = Rc::new(start);
+            let middle = Node::calculate_next_nodes(start, &heuristic)
+                .into_iter()
+                .find(|node| node.state.blank() == 5)
+                .unwrap();
+            let last = Node::calculate_next_nodes(Rc::new(middle), &heuristic)
+                .into_iter()
+                .find(|node| node.state.blank() == 4)
+                .unwrap();
+
+            assert_eq!(
+                last.retrieve_path(),
+                vec![
+                    Puzzle {
+                        data: vec![1, 2, 3, 8, 4, 5, 7, 6, 0],
+                        width: 3,
+                        height: 3,
+                    },
+                    Puzzle {
+                        data: vec![1, 2, 3, 8, 4, 0, 7, 6, 5],
+                        width: 3,
+                        height: 3,
+                    },
+                    Puzzle::get_final_state(3, 3),
+                ]
+            );
+        }
+    }
 }
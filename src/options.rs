@@ -1,22 +1,33 @@
 extern crate clap;
 extern crate structopt;
 
+use crate::expression::Expression;
+use crate::state::MAX_TILES;
 use clap::arg_enum;
 use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 use structopt::StructOpt;
 
+/*
+ ** subcommands may be shortened (gen), which also keeps clap from taking a puzzle
+ ** file named like one of them (generated_n-puzzle) for a misspelling
+ */
 #[derive(StructOpt, Debug)]
-#[structopt(about = "A* driven n puzzle solver.")]
+#[structopt(
+    about = "A* driven n puzzle solver.",
+    raw(setting = "clap::AppSettings::InferSubcommands")
+)]
 pub struct Opt {
     #[structopt(
         short = "h",
         long = "heuristic",
         default_value = "manhattan",
-        raw(possible_values = "&HeuristicValues::variants()"),
-        case_insensitive = true
+        raw(global = "true"),
+        help = "hamming, manhattan, linear, m2l, pdb or walking, or a combination of them such as max(manhattan,walking) or manhattan+2*linear"
     )]
-    pub heuristic: HeuristicValues,
+    pub heuristic: Expression,
 
     #[structopt(
         short = "a",
@@ -27,20 +38,309 @@ pub struct Opt {
     )]
     pub algorithm: AlgorithmValues,
 
-    #[structopt(short = "g", long = "generate", conflicts_with = "FILE")]
-    pub size: Option<usize>,
+    #[structopt(
+        short = "w",
+        long = "weight",
+        help = "Weight of the heuristic, f = g + weight * h: the path found by astar and ida is at most weight times the shortest one, ara starts from it (3 by default) and lowers it down to 1, the others ignore it",
+        parse(try_from_str = "parse_weight")
+    )]
+    pub weight: Option<f64>,
+
+    #[structopt(
+        short = "t",
+        long = "threads",
+        help = "Number of threads of hda, one per core by default",
+        parse(try_from_str = "parse_threads")
+    )]
+    pub threads: Option<usize>,
+
+    #[structopt(
+        long = "max-states",
+        help = "Number of states kept in memory at once: sma drops the least promising ones past it, ida ignores it, the other searches give up"
+    )]
+    pub max_states: Option<usize>,
+
+    #[structopt(
+        long = "max-memory",
+        help = "Same as --max-states, in bytes with an optional K, M or G suffix (512M)",
+        parse(try_from_str = "parse_memory")
+    )]
+    pub max_memory: Option<usize>,
+
+    #[structopt(
+        long = "timeout",
+        raw(global = "true"),
+        help = "Give up after this long, in seconds or with a ms, s, m or h suffix (500ms, 2m): the statistics so far are still shown, as on Ctrl-C",
+        parse(try_from_str = "parse_duration")
+    )]
+    pub timeout: Option<Duration>,
+
+    #[structopt(
+        long = "output",
+        default_value = "boards",
+        help = "How the solution is printed: boards (every step), moves (the direction the blank moves, as a ULDR string) or tiles (the tile slid at each step)",
+        raw(possible_values = "&OutputValues::variants()"),
+        case_insensitive = true
+    )]
+    pub output: OutputValues,
+
+    #[structopt(
+        long = "format",
+        default_value = "text",
+        help = "text, or json for a single document with the board, the moves, every state and the statistics, and nothing else on the standard output",
+        raw(possible_values = "&FormatValues::variants()"),
+        case_insensitive = true
+    )]
+    pub format: FormatValues,
+
+    #[structopt(
+        long = "goal",
+        raw(global = "true"),
+        help = "Goal layout: snail, rowmajor (blank last) or blankfirst (row-major, blank first)",
+        raw(possible_values = "&GoalValues::variants()"),
+        case_insensitive = true,
+        conflicts_with = "goal_file"
+    )]
+    pub goal: Option<GoalValues>,
+
+    #[structopt(
+        long = "goal-file",
+        raw(global = "true"),
+        help = "Read the goal layout from a puzzle file",
+        parse(from_os_str),
+        conflicts_with = "goal"
+    )]
+    pub goal_file: Option<PathBuf>,
+
+    #[structopt(
+        short = "g",
+        long = "generate",
+        help = "Generate a random board, either a side length (3) or width x height (3x5)",
+        conflicts_with = "FILE"
+    )]
+    pub size: Option<BoardSize>,
 
     #[structopt(name = "FILE", parse(from_os_str), conflicts_with = "generate")]
     pub file: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    #[structopt(
+        name = "verify",
+        about = "Replays a move sequence on a puzzle and checks that it reaches the goal"
+    )]
+    Verify {
+        #[structopt(
+            name = "PUZZLE",
+            help = "Puzzle file, in the same format as FILE",
+            parse(from_os_str)
+        )]
+        file: PathBuf,
+
+        #[structopt(
+            name = "MOVES",
+            help = "Directions the blank moves, as printed by --output moves (RDLU)",
+            required_unless = "moves_file",
+            conflicts_with = "moves_file"
+        )]
+        moves: Option<String>,
+
+        #[structopt(
+            long = "moves-file",
+            help = "Read the moves from a file instead",
+            parse(from_os_str)
+        )]
+        moves_file: Option<PathBuf>,
+
+        #[structopt(
+            long = "optimal",
+            help = "Also solve the puzzle with astar and -h to tell whether the sequence is one of the shortest"
+        )]
+        optimal: bool,
+    },
+
+    #[structopt(
+        name = "generate",
+        about = "Writes a random board to the standard output, in the format the solver reads"
+    )]
+    Generate {
+        #[structopt(
+            name = "SIZE",
+            help = "Either a side length (3) or width x height (3x5)"
+        )]
+        size: BoardSize,
+
+        #[structopt(
+            long = "seed",
+            help = "Seed of the random generator, the same seed gives the same board with the same options and version. Random by default, and written in the header"
+        )]
+        seed: Option<u64>,
+
+        #[structopt(
+            short = "s",
+            long = "solvable",
+            help = "Only a board that can reach the goal",
+            conflicts_with = "unsolvable"
+        )]
+        solvable: bool,
+
+        #[structopt(
+            short = "u",
+            long = "unsolvable",
+            help = "Only a board that can't reach the goal. Either one at random without -s or -u",
+            conflicts_with = "solvable"
+        )]
+        unsolvable: bool,
+
+        #[structopt(
+            long = "walk",
+            help = "Random moves from the goal, never undoing the previous one, instead of a uniformly random layout. An unsolvable board then has two tiles swapped"
+        )]
+        walk: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl FromStr for BoardSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BoardSize, String> {
+        let parse_side = |side: &str| {
+            side.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid board size {:?}", s))
+        };
+        let (width, height) = match s.split_once(['x', 'X']) {
+            Some((width, height)) => (parse_side(width)?, parse_side(height)?),
+            None => {
+                let side = parse_side(s)?;
+                (side, side)
+            }
+        };
+        if width < 2 || height < 2 {
+            return Err(format!(
+                "board {}x{} too small, sides must be at least 2",
+                width, height
+            ));
+        }
+        if width.saturating_mul(height) > MAX_TILES {
+            return Err(format!(
+                "board {}x{} too large, at most {} tiles",
+                width, height, MAX_TILES
+            ));
+        }
+
+        Ok(BoardSize { width, height })
+    }
+}
+
+fn parse_weight(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(weight) if weight >= 1.0 && weight.is_finite() => Ok(weight),
+        _ => Err(format!(
+            "invalid weight {:?}, it must be a number of at least 1",
+            s
+        )),
+    }
+}
+
+fn parse_memory(s: &str) -> Result<usize, String> {
+    let (number, unit) = match s.trim().to_uppercase() {
+        size if size.ends_with('K') => (size[..size.len() - 1].to_string(), 1 << 10),
+        size if size.ends_with('M') => (size[..size.len() - 1].to_string(), 1 << 20),
+        size if size.ends_with('G') => (size[..size.len() - 1].to_string(), 1 << 30),
+        size => (size, 1),
+    };
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_mul(unit))
+        .ok_or(format!(
+            "invalid memory size {:?}, expected a number of bytes such as 4096, 512M or 2G",
+            s
+        ))
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, unit) = match s {
+        duration if duration.ends_with("ms") => (&duration[..duration.len() - 2], 0.001),
+        duration if duration.ends_with('s') => (&duration[..duration.len() - 1], 1.0),
+        duration if duration.ends_with('m') => (&duration[..duration.len() - 1], 60.0),
+        duration if duration.ends_with('h') => (&duration[..duration.len() - 1], 3600.0),
+        duration => (duration, 1.0),
+    };
+    match number.parse::<f64>() {
+        Ok(number) if number > 0.0 && number.is_finite() => {
+            Duration::try_from_secs_f64(number * unit).map_err(|error| error.to_string())
+        }
+        _ => Err(format!(
+            "invalid duration {:?}, expected a positive time such as 30, 500ms, 2m or 1h",
+            s
+        )),
+    }
+}
+
+fn parse_threads(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(threads) if threads >= 1 => Ok(threads),
+        _ => Err(format!(
+            "invalid number of threads {:?}, it must be at least 1",
+            s
+        )),
+    }
+}
+
+impl fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
 }
 
 arg_enum! {
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
     pub enum HeuristicValues {
         Hamming,
         Manhattan,
         Linear,
         M2L,
+        Pdb,
+        Walking,
+    }
+}
+
+arg_enum! {
+    #[derive(Debug)]
+    pub enum GoalValues {
+        Snail,
+        RowMajor,
+        BlankFirst,
+    }
+}
+
+arg_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum OutputValues {
+        Boards,
+        Moves,
+        Tiles,
+    }
+}
+
+arg_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum FormatValues {
+        Text,
+        Json,
     }
 }
 
@@ -49,6 +349,13 @@ arg_enum! {
     pub enum AlgorithmValues {
         Greedy,
         Astar,
+        Ida,
+        Ara,
+        Bidirectional,
+        Bfs,
+        UniformCost,
+        Hda,
+        Sma,
     }
 }
 
@@ -56,12 +363,130 @@ impl fmt::Display for Opt {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "Heuristic : {}", self.heuristic)?;
         writeln!(f, "Algorithm : {}", self.algorithm)?;
-        if self.size != None {
-            writeln!(f, "Size : {}", self.size.unwrap())?;
+        if let Some(weight) = self.weight {
+            writeln!(f, "Weight : {}", weight)?;
+        }
+        if let Some(threads) = self.threads {
+            writeln!(f, "Threads : {}", threads)?;
+        }
+        if let Some(max_states) = self.max_states {
+            writeln!(f, "Max states : {}", max_states)?;
         }
-        if self.file != None {
-            writeln!(f, "File : {:?}", self.file.clone().unwrap())?;
+        if let Some(max_memory) = self.max_memory {
+            writeln!(f, "Max memory : {} bytes", max_memory)?;
+        }
+        if let Some(timeout) = self.timeout {
+            writeln!(f, "Timeout : {:?}", timeout)?;
+        }
+        writeln!(f, "Output : {}", self.output)?;
+        match (&self.goal, &self.goal_file) {
+            (_, Some(goal_file)) => writeln!(f, "Goal : {:?}", goal_file)?,
+            (Some(goal), None) => writeln!(f, "Goal : {}", goal)?,
+            (None, None) => writeln!(f, "Goal : {}", GoalValues::Snail)?,
+        }
+        if let Some(size) = self.size {
+            writeln!(f, "Size : {}", size)?;
+        }
+        if let Some(file) = &self.file {
+            writeln!(f, "File : {:?}", file)?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod options_tests {
+    mod board_size {
+        use crate::options::BoardSize;
+
+        #[test]
+        fn square() {
+            assert_eq!(
+                "4".parse(),
+                Ok(BoardSize {
+                    width: 4,
+                    height: 4
+                })
+            );
+        }
+
+        #[test]
+        fn rectangle() {
+            assert_eq!(
+                "3x5".parse(),
+                Ok(BoardSize {
+                    width: 3,
+                    height: 5
+                })
+            );
+        }
+
+        #[test]
+        fn errors() {
+            assert!("3x".parse::<BoardSize>().is_err());
+            assert!("three".parse::<BoardSize>().is_err());
+            assert!("1x4".parse::<BoardSize>().is_err());
+            assert!("300x300".parse::<BoardSize>().is_err());
+        }
+    }
+
+    mod parse_weight {
+        use crate::options::parse_weight;
+
+        #[test]
+        fn at_least_one() {
+            assert_eq!(parse_weight("1.5"), Ok(1.5));
+            assert_eq!(parse_weight("3"), Ok(3.0));
+            assert!(parse_weight("0.5").is_err());
+            assert!(parse_weight("inf").is_err());
+            assert!(parse_weight("heavy").is_err());
+        }
+    }
+
+    mod parse_memory {
+        use crate::options::parse_memory;
+
+        #[test]
+        fn units() {
+            assert_eq!(parse_memory("4096"), Ok(4096));
+            assert_eq!(parse_memory("64k"), Ok(64 << 10));
+            assert_eq!(parse_memory("512M"), Ok(512 << 20));
+            assert_eq!(parse_memory("2G"), Ok(2 << 30));
+            assert!(parse_memory("M").is_err());
+            assert!(parse_memory("1.5G").is_err());
+            assert!(parse_memory("lots").is_err());
+        }
+    }
+
+    mod parse_threads {
+        use crate::options::parse_threads;
+
+        #[test]
+        fn at_least_one() {
+            assert_eq!(parse_threads("1"), Ok(1));
+            assert_eq!(parse_threads("16"), Ok(16));
+            assert!(parse_threads("0").is_err());
+            assert!(parse_threads("-2").is_err());
+            assert!(parse_threads("many").is_err());
+        }
+    }
+
+    mod parse_duration {
+        use crate::options::parse_duration;
+        use std::time::Duration;
+
+        #[test]
+        fn units() {
+            assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+            assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+            assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+            assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+            assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+            assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+            assert!(parse_duration("0").is_err());
+            assert!(parse_duration("-5s").is_err());
+            assert!(parse_duration("ms").is_err());
+            assert!(parse_duration("soon").is_err());
+        }
+    }
+}
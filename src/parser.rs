@@ -1,104 +1,353 @@
+use crate::goal::Goal;
 use crate::puzzle::Puzzle;
+use crate::state::MAX_TILES;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    NoData,
+    EmptyLine,
+    UnexpectedCharacter(char),
+    InvalidNumber(String),
+    BadSizeLine,
+    SizeTooSmall(usize),
+    SizeTooLarge { width: usize, height: usize },
+    WrongRowWidth { expected: usize, found: usize },
+    WrongNumberOfRows { expected: usize, found: usize },
+    TileOutOfRange(usize),
+    DuplicateTile(usize),
+    MissingTile(usize),
+    WrongGoalSize { width: usize, height: usize },
+    Unsolvable,
+}
 
-pub fn remove_comments(mut lines: Vec<String>) -> Vec<String> {
-    //unsafe if used before check_empty_lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub line: usize,
+    pub column: usize,
+}
 
-    lines.retain(|ref line| line.chars().nth(0).unwrap() != '#');
-    lines.iter_mut().for_each(|ref mut line| {
-        let pos = match line.chars().position(|c| c == '#') {
-            Some(pos) => pos,
-            None => line.len(),
-        };
-        line.truncate(pos);
-    });
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub value: usize,
+    pub line: usize,
+    pub column: usize,
+}
 
-    lines
+/*
+ ** "3" is a 3x3 board, "3 5" is 3 tiles wide and 5 tall
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub width: Tile,
+    pub height: Tile,
 }
 
-pub fn check_empty_lines(lines: &[String]) {
-    lines.iter().for_each(|line| {
-        if line.is_empty() {
-            panic!("There is an empty line in the file\n")
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::NoData => write!(f, "no data"),
+            ParseErrorKind::EmptyLine => write!(f, "empty line"),
+            ParseErrorKind::UnexpectedCharacter(c) => write!(
+                f,
+                "unexpected character {:?} (only spaces and numbers are allowed beside comments)",
+                c
+            ),
+            ParseErrorKind::InvalidNumber(token) => write!(f, "invalid number {}", token),
+            ParseErrorKind::BadSizeLine => write!(
+                f,
+                "first line should only contain the size, or the width and the height"
+            ),
+            ParseErrorKind::SizeTooSmall(size) => {
+                write!(f, "side of size {} too small, must be at least 2", size)
+            }
+            ParseErrorKind::SizeTooLarge { width, height } => write!(
+                f,
+                "board of {}x{} too large, at most {} tiles",
+                width, height, MAX_TILES
+            ),
+            ParseErrorKind::WrongRowWidth { expected, found } => {
+                write!(
+                    f,
+                    "expected {} values on this row, found {}",
+                    expected, found
+                )
+            }
+            ParseErrorKind::WrongNumberOfRows { expected, found } => {
+                write!(f, "expected {} rows, found {}", expected, found)
+            }
+            ParseErrorKind::TileOutOfRange(value) => {
+                write!(f, "tile {} is out of range for the chosen size", value)
+            }
+            ParseErrorKind::DuplicateTile(value) => write!(f, "duplicate tile {}", value),
+            ParseErrorKind::MissingTile(value) => write!(f, "missing tile {}", value),
+            ParseErrorKind::WrongGoalSize { width, height } => {
+                write!(
+                    f,
+                    "size does not match the goal of size {}x{}",
+                    width, height
+                )
+            }
+            ParseErrorKind::Unsolvable => write!(f, "unsolvable puzzle"),
         }
-    })
+    }
 }
 
-pub fn check_empty_vec(lines: &[String]) {
-    if lines.is_empty() {
-        panic!("No data.\n")
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.kind, self.line, self.column)
     }
 }
 
-pub fn check_only_numbers_and_spaces(lines: &[String]) {
-    lines.iter()
-		 .for_each(|line| {
-		 	if !line.chars().all(|c| c.is_digit(10) || c.is_whitespace()) {
-		 		panic!("Unexpected character in file (only spaces and numbers are allowed beside comments)\n")
-		 	}
-		 })
+impl Error for ParseError {}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, line: usize, column: usize) -> ParseError {
+        ParseError { kind, line, column }
+    }
 }
 
-pub fn check_values_are_incremental(size: usize, data: Vec<usize>) {
-    let mut all_the_values: Vec<usize> = (0..(size * size)).collect();
+/*
+ ** lines and columns are numbered from 1, like in any text editor
+ */
+pub fn remove_comments(lines: Vec<String>) -> Vec<(usize, String)> {
+    lines
+        .into_iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim_start().starts_with('#'))
+        .map(|(i, mut line)| {
+            let pos = line.find('#').unwrap_or(line.len());
+            line.truncate(pos);
+            (i + 1, line)
+        })
+        .collect()
+}
 
-    data.iter().for_each(|value| {
-        all_the_values.remove(
-            all_the_values
-                .clone()
-                .iter()
-                .position(|&x| x == *value)
-                .unwrap(),
-        );
-    });
+pub fn check_empty_lines(lines: &[String]) -> Result<(), ParseError> {
+    match lines.iter().position(|line| line.trim().is_empty()) {
+        Some(i) => Err(ParseError::new(ParseErrorKind::EmptyLine, i + 1, 1)),
+        None => Ok(()),
+    }
+}
 
-    if !all_the_values.is_empty() {
-        panic!("All the values needed for chosen size are not present.\n")
+pub fn check_empty_vec(lines: &[(usize, String)]) -> Result<(), ParseError> {
+    if lines.is_empty() {
+        return Err(ParseError::new(ParseErrorKind::NoData, 1, 1));
     }
+    Ok(())
 }
 
-pub fn check_values_form_correct_square(size: usize, data: Vec<usize>) {
-    if size < 3 {
-        panic!("Square too small, must be at least 3 of size !\n")
+pub fn check_only_numbers_and_spaces(lines: &[(usize, String)]) -> Result<(), ParseError> {
+    for (number, line) in lines {
+        if let Some((column, c)) = line
+            .chars()
+            .enumerate()
+            .find(|(_, c)| !c.is_ascii_digit() && !c.is_whitespace())
+        {
+            return Err(ParseError::new(
+                ParseErrorKind::UnexpectedCharacter(c),
+                *number,
+                column + 1,
+            ));
+        }
     }
-    if data.len() != size * size {
-        panic!("Wrong number of lines !\n")
+    Ok(())
+}
+
+fn tokenize(number: usize, line: &str) -> Result<Vec<Tile>, ParseError> {
+    let mut tiles = vec![];
+    let mut token = String::new();
+
+    for (column, c) in line.chars().chain(Some(' ')).enumerate() {
+        if !c.is_whitespace() {
+            token.push(c);
+            continue;
+        }
+        if token.is_empty() {
+            continue;
+        }
+
+        let first = column - token.chars().count() + 1;
+        let value = token.parse::<usize>().map_err(|_| {
+            ParseError::new(ParseErrorKind::InvalidNumber(token.clone()), number, first)
+        })?;
+        tiles.push(Tile {
+            value,
+            line: number,
+            column: first,
+        });
+        token.clear();
     }
+
+    Ok(tiles)
 }
 
-pub fn get_data(mut lines: Vec<String>) -> (usize, Vec<usize>) {
-    let size = match lines.remove(0).parse::<usize>() {
-        Err(_e) => panic!("first line should only contain one value\n"),
-        Ok(x) => x,
+pub fn get_data(mut lines: Vec<(usize, String)>) -> Result<(Header, Vec<Vec<Tile>>), ParseError> {
+    if lines.is_empty() {
+        return Err(ParseError::new(ParseErrorKind::NoData, 1, 1));
+    }
+    let (number, size_line) = lines.remove(0);
+    let header = match tokenize(number, &size_line)?.as_slice() {
+        [size] => Header {
+            width: *size,
+            height: *size,
+        },
+        [width, height] => Header {
+            width: *width,
+            height: *height,
+        },
+        [_, _, extra, ..] => {
+            return Err(ParseError::new(
+                ParseErrorKind::BadSizeLine,
+                number,
+                extra.column,
+            ))
+        }
+        [] => return Err(ParseError::new(ParseErrorKind::BadSizeLine, number, 1)),
     };
 
-    let mut data = vec![];
-    for line in lines {
-        data.append(
-            &mut line
-                .split_whitespace()
-                .map(|token| -> usize {
-                    token
-                        .parse::<usize>()
-                        .expect("Unable to parse data into u32\n")
-                })
-                .collect(),
-        )
+    let mut rows = vec![];
+    for (number, line) in lines {
+        rows.push(tokenize(number, &line)?);
     }
 
-    (size, data)
+    Ok((header, rows))
 }
 
-pub fn parse(mut lines: Vec<String>) -> Puzzle {
+pub fn check_values_form_correct_rectangle(
+    header: &Header,
+    rows: &[Vec<Tile>],
+) -> Result<(), ParseError> {
+    for side in [header.width, header.height] {
+        if side.value < 2 {
+            return Err(ParseError::new(
+                ParseErrorKind::SizeTooSmall(side.value),
+                side.line,
+                side.column,
+            ));
+        }
+    }
+    let (width, height) = (header.width.value, header.height.value);
+    if width.saturating_mul(height) > MAX_TILES {
+        return Err(ParseError::new(
+            ParseErrorKind::SizeTooLarge { width, height },
+            header.width.line,
+            header.width.column,
+        ));
+    }
+
+    let expected = header.width.value;
+    for row in rows.iter() {
+        if row.len() != expected {
+            let column = match row.get(expected) {
+                Some(extra) => extra.column,
+                None => row.last().map_or(1, |tile| tile.column),
+            };
+            return Err(ParseError::new(
+                ParseErrorKind::WrongRowWidth {
+                    expected,
+                    found: row.len(),
+                },
+                row.first().map_or(header.width.line + 1, |tile| tile.line),
+                column,
+            ));
+        }
+    }
+
+    let expected = header.height.value;
+    if rows.len() != expected {
+        let line = match rows.get(expected) {
+            Some(extra) => extra[0].line,
+            None => rows.last().map_or(header.height.line, |row| row[0].line),
+        };
+        return Err(ParseError::new(
+            ParseErrorKind::WrongNumberOfRows {
+                expected,
+                found: rows.len(),
+            },
+            line,
+            1,
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn check_values_are_incremental(header: &Header, tiles: &[Tile]) -> Result<(), ParseError> {
+    let len = header.width.value * header.height.value;
+    let mut seen = vec![false; len];
+
+    for tile in tiles {
+        if tile.value >= len {
+            return Err(ParseError::new(
+                ParseErrorKind::TileOutOfRange(tile.value),
+                tile.line,
+                tile.column,
+            ));
+        }
+        if seen[tile.value] {
+            return Err(ParseError::new(
+                ParseErrorKind::DuplicateTile(tile.value),
+                tile.line,
+                tile.column,
+            ));
+        }
+        seen[tile.value] = true;
+    }
+
+    match seen.iter().position(|&present| !present) {
+        Some(value) => Err(ParseError::new(
+            ParseErrorKind::MissingTile(value),
+            header.width.line,
+            header.width.column,
+        )),
+        None => Ok(()),
+    }
+}
+
+fn read_board(lines: Vec<String>) -> Result<(Header, Vec<usize>), ParseError> {
+    check_empty_lines(&lines)?;
+    let lines = remove_comments(lines);
+    check_empty_vec(&lines)?;
+    check_only_numbers_and_spaces(&lines)?;
+    let (header, rows) = get_data(lines)?;
+    check_values_form_correct_rectangle(&header, &rows)?;
+    let tiles: Vec<Tile> = rows.into_iter().flatten().collect();
+    check_values_are_incremental(&header, &tiles)?;
+
+    Ok((header, tiles.iter().map(|tile| tile.value).collect()))
+}
+
+pub fn parse_goal(lines: Vec<String>) -> Result<Puzzle, ParseError> {
+    let (header, data) = read_board(lines)?;
+
+    Ok(Puzzle {
+        data,
+        width: header.width.value,
+        height: header.height.value,
+    })
+}
+
+pub fn parse(lines: Vec<String>, goal: &Goal) -> Result<Puzzle, ParseError> {
     //bad naming, possible confusion with str::parse
-    check_empty_lines(&lines);
-    lines = remove_comments(lines);
-    check_empty_vec(&lines);
-    check_only_numbers_and_spaces(&lines);
-    let (size, data) = get_data(lines);
-    check_values_are_incremental(size, data.clone());
-    check_values_form_correct_square(size, data.clone());
-
-    Puzzle::new_from_file(data, size)
+    let (header, data) = read_board(lines)?;
+    let (width, height) = (header.width.value, header.height.value);
+    let (line, column) = (header.width.line, header.width.column);
+    let goal_puzzle = goal.state(width, height).ok_or_else(|| {
+        let kind = match goal {
+            Goal::Custom(puzzle) => ParseErrorKind::WrongGoalSize {
+                width: puzzle.width,
+                height: puzzle.height,
+            },
+            _ => ParseErrorKind::WrongGoalSize { width, height },
+        };
+        ParseError::new(kind, line, column)
+    })?;
+
+    Puzzle::new_from_file(data, width, height, &goal_puzzle)
+        .ok_or_else(|| ParseError::new(ParseErrorKind::Unsolvable, line, column))
 }
 
 #[cfg(test)]
@@ -106,43 +355,178 @@ mod parser_tests {
     mod parse {
         use crate::parser::*;
 
+        fn to_lines(lines: &[&str]) -> Vec<String> {
+            lines.iter().map(|line| line.to_string()).collect()
+        }
+
+        #[test]
+        fn error_no_size() {
+            let lines = to_lines(&["0  3  4", "1 5     6", "   2 7 8   "]);
+
+            assert_eq!(
+                parse(lines, &Goal::Snail),
+                Err(ParseError::new(ParseErrorKind::BadSizeLine, 1, 7))
+            );
+        }
+
         #[test]
-        #[should_panic]
-        fn panic_no_size() {
+        fn no_error() {
+            let lines = to_lines(&["3", "1 2 3", "8 0 4", "7 6 5"]);
+
+            assert_eq!(
+                parse(lines, &Goal::Snail),
+                Ok(Puzzle::get_final_state(3, 3))
+            );
+        }
+
+        #[test]
+        fn with_comments() {
+            let lines = to_lines(&[
+                "# This puzzle is solvable",
+                "3 # size",
+                "1 2 3",
+                "8 0 4 # blank in the middle",
+                "7 6 5",
+            ]);
+
+            assert_eq!(
+                parse(lines, &Goal::Snail),
+                Ok(Puzzle::get_final_state(3, 3))
+            );
+        }
+
+        #[test]
+        fn error_duplicate_tile_position() {
+            let lines = to_lines(&["# comment", "3", "1 2 3", "8 0 4", "7 6 6"]);
+
+            assert_eq!(
+                parse(lines, &Goal::Snail),
+                Err(ParseError::new(ParseErrorKind::DuplicateTile(6), 5, 5))
+            );
+        }
+
+        #[test]
+        fn row_major_goal() {
+            let lines = to_lines(&["3", "1 2 3", "4 5 6", "7 8 0"]);
+
+            assert_eq!(
+                parse(lines, &Goal::RowMajor),
+                Ok(Goal::RowMajor.state(3, 3).unwrap())
+            );
+        }
+
+        #[test]
+        fn rectangle() {
+            let lines = to_lines(&["4 2", "1 2 3 4", "0 7 6 5"]);
+
+            assert_eq!(
+                parse(lines, &Goal::Snail),
+                Ok(Puzzle {
+                    data: vec![1, 2, 3, 4, 0, 7, 6, 5],
+                    width: 4,
+                    height: 2,
+                })
+            );
+        }
+
+        #[test]
+        fn error_unsolvable_rectangle() {
+            let lines = to_lines(&["4 2", "1 2 3 4", "0 7 5 6"]);
+
+            assert_eq!(
+                parse(lines, &Goal::Snail),
+                Err(ParseError::new(ParseErrorKind::Unsolvable, 1, 1))
+            );
+        }
+
+        #[test]
+        fn error_wrong_goal_size() {
+            let lines = to_lines(&["3", "1 2 3", "8 0 4", "7 6 5"]);
+            let goal = Goal::Custom(Puzzle::get_final_state(4, 4));
+
+            assert_eq!(
+                parse(lines, &goal),
+                Err(ParseError::new(
+                    ParseErrorKind::WrongGoalSize {
+                        width: 4,
+                        height: 4
+                    },
+                    1,
+                    1
+                ))
+            );
+        }
+
+        #[test]
+        fn error_unsolvable() {
+            let lines = to_lines(&["3", "0 1 2", "3 4 5", "6 7 8"]);
+
+            assert_eq!(
+                parse(lines, &Goal::Snail),
+                Err(ParseError::new(ParseErrorKind::Unsolvable, 1, 1))
+            );
+        }
+    }
+
+    mod parse_goal {
+        use crate::parser::*;
+
+        #[test]
+        fn unsolvable_layout_is_accepted() {
             let lines: Vec<String> = vec![
-                "0  3  4".to_string(),
-                "1 5     6".to_string(),
-                "   2 7 8   ".to_string(),
+                "3".to_string(),
+                "0 1 2".to_string(),
+                "3 4 5".to_string(),
+                "6 7 8".to_string(),
             ];
-            parse(lines);
+
+            assert_eq!(parse_goal(lines), Ok(Goal::BlankFirst.state(3, 3).unwrap()));
         }
+    }
+
+    mod remove_comments {
+        use crate::parser::*;
 
         #[test]
-        fn no_panic() {
+        fn keeps_line_numbers() {
             let lines: Vec<String> = vec![
-                "3".to_string(),
+                "# header".to_string(),
+                "3 # size".to_string(),
                 "1 2 3".to_string(),
-                "8 0 4".to_string(),
-                "7 6 5".to_string(),
             ];
-            parse(lines);
+
+            assert_eq!(
+                remove_comments(lines),
+                vec![(2, "3 ".to_string()), (3, "1 2 3".to_string())]
+            );
+        }
+
+        #[test]
+        fn no_error() {
+            let lines: Vec<String> = vec!["1".to_string()];
+
+            assert_eq!(remove_comments(lines), vec![(1, "1".to_string())]);
         }
     }
 
-    mod remove_comments {
+    mod check_empty_lines {
         use crate::parser::*;
 
         #[test]
-        #[should_panic]
-        fn panic_because_empty_line() {
-            let lines: Vec<String> = vec!["".to_string()];
-            remove_comments(lines);
+        fn error_because_empty_line() {
+            let lines: Vec<String> = vec!["3".to_string(), "".to_string()];
+
+            assert_eq!(
+                check_empty_lines(&lines),
+                Err(ParseError::new(ParseErrorKind::EmptyLine, 2, 1))
+            );
         }
 
         #[test]
-        fn no_panic() {
+        fn no_error() {
             let lines: Vec<String> = vec!["1".to_string()];
-            remove_comments(lines);
+
+            assert!(check_empty_lines(&lines).is_ok());
         }
     }
 
@@ -150,94 +534,251 @@ mod parser_tests {
         use crate::parser::*;
 
         #[test]
-        #[should_panic]
-        fn panic_because_empty_vec() {
-            let lines: Vec<String> = vec![];
-            check_empty_vec(&lines);
+        fn error_because_empty_vec() {
+            let lines: Vec<(usize, String)> = vec![];
+
+            assert_eq!(
+                check_empty_vec(&lines),
+                Err(ParseError::new(ParseErrorKind::NoData, 1, 1))
+            );
         }
 
         #[test]
-        fn no_panic() {
-            let lines: Vec<String> = vec!["1".to_string()];
-            check_empty_vec(&lines);
+        fn no_error() {
+            let lines: Vec<(usize, String)> = vec![(1, "1".to_string())];
+
+            assert!(check_empty_vec(&lines).is_ok());
+        }
+    }
+
+    mod check_only_numbers_and_spaces {
+        use crate::parser::*;
+
+        #[test]
+        fn error_because_letter() {
+            let lines: Vec<(usize, String)> = vec![(1, "3".to_string()), (4, "1 a 3".to_string())];
+
+            assert_eq!(
+                check_only_numbers_and_spaces(&lines),
+                Err(ParseError::new(
+                    ParseErrorKind::UnexpectedCharacter('a'),
+                    4,
+                    3
+                ))
+            );
         }
     }
 
     mod check_values_are_incremental {
         use crate::parser::*;
 
+        fn to_tiles(values: Vec<usize>) -> Vec<Tile> {
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| Tile {
+                    value,
+                    line: 2,
+                    column: 2 * i + 1,
+                })
+                .collect()
+        }
+
+        fn size(value: usize) -> Header {
+            let side = Tile {
+                value,
+                line: 1,
+                column: 1,
+            };
+            Header {
+                width: side,
+                height: side,
+            }
+        }
+
         #[test]
-        #[should_panic]
-        fn panic_because_value_out_of_scope() {
-            let size: usize = 2;
-            let tab = vec![0, 1, 2, 5];
-            check_values_are_incremental(size, tab);
+        fn error_because_value_out_of_scope() {
+            let tab = to_tiles(vec![0, 1, 2, 5]);
+
+            assert_eq!(
+                check_values_are_incremental(&size(2), &tab),
+                Err(ParseError::new(ParseErrorKind::TileOutOfRange(5), 2, 7))
+            );
         }
 
         #[test]
-        #[should_panic]
-        fn panic_because_two_time_same_value() {
-            let size: usize = 2;
-            let tab = vec![0, 1, 2, 2];
-            check_values_are_incremental(size, tab);
+        fn error_because_two_time_same_value() {
+            let tab = to_tiles(vec![0, 1, 2, 2]);
+
+            assert_eq!(
+                check_values_are_incremental(&size(2), &tab),
+                Err(ParseError::new(ParseErrorKind::DuplicateTile(2), 2, 7))
+            );
         }
 
         #[test]
-        #[should_panic]
-        fn panic_because_too_few_values() {
-            let size: usize = 2;
-            let tab = vec![0, 1, 2];
-            check_values_are_incremental(size, tab);
+        fn error_because_too_few_values() {
+            let tab = to_tiles(vec![0, 1, 2]);
+
+            assert_eq!(
+                check_values_are_incremental(&size(2), &tab),
+                Err(ParseError::new(ParseErrorKind::MissingTile(3), 1, 1))
+            );
         }
 
         #[test]
-        fn no_panic() {
-            let size: usize = 2;
-            let tab = vec![0, 1, 2, 3];
-            check_values_are_incremental(size, tab);
+        fn no_error() {
+            let tab = to_tiles(vec![0, 1, 2, 3]);
+
+            assert!(check_values_are_incremental(&size(2), &tab).is_ok());
+        }
+
+        #[test]
+        fn rectangle() {
+            let mut header = size(2);
+            header.height.value = 3;
+
+            assert!(
+                check_values_are_incremental(&header, &to_tiles(vec![5, 4, 3, 2, 1, 0])).is_ok()
+            );
+            assert_eq!(
+                check_values_are_incremental(&header, &to_tiles(vec![0, 1, 2, 3])),
+                Err(ParseError::new(ParseErrorKind::MissingTile(4), 1, 1))
+            );
         }
     }
 
-    mod check_values_form_correct_square {
+    mod check_values_form_correct_rectangle {
         use crate::parser::*;
 
+        fn get_rows(lines: &[&str]) -> (Header, Vec<Vec<Tile>>) {
+            get_data(
+                lines
+                    .iter()
+                    .enumerate()
+                    .map(|(i, line)| (i + 1, line.to_string()))
+                    .collect(),
+            )
+            .unwrap()
+        }
+
         #[test]
-        #[should_panic]
-        fn panic_because_too_few_values_in_one_line() {
-            let size: usize = 3;
-            let tab = vec![0, 1, 2, 3, 5, 6, 7, 8];
-            check_values_form_correct_square(size, tab);
+        fn error_because_too_few_values_in_one_line() {
+            let (size, rows) = get_rows(&["3", "0 1 2", "3 5", "6 7 8"]);
+
+            assert_eq!(
+                check_values_form_correct_rectangle(&size, &rows),
+                Err(ParseError::new(
+                    ParseErrorKind::WrongRowWidth {
+                        expected: 3,
+                        found: 2
+                    },
+                    3,
+                    3
+                ))
+            );
         }
 
         #[test]
-        #[should_panic]
-        fn panic_because_too_much_values_in_one_line() {
-            let size: usize = 3;
-            let tab = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
-            check_values_form_correct_square(size, tab);
+        fn error_because_too_much_values_in_one_line() {
+            let (size, rows) = get_rows(&["3", "0 1 2", "3 4 5", "6 7 8 9"]);
+
+            assert_eq!(
+                check_values_form_correct_rectangle(&size, &rows),
+                Err(ParseError::new(
+                    ParseErrorKind::WrongRowWidth {
+                        expected: 3,
+                        found: 4
+                    },
+                    4,
+                    7
+                ))
+            );
         }
 
         #[test]
-        #[should_panic]
-        fn panic_because_wrong_number_of_lines() {
-            let size: usize = 3;
-            let tab = vec![0, 1, 2, 3, 4, 5];
-            check_values_form_correct_square(size, tab);
+        fn error_because_wrong_number_of_lines() {
+            let (size, rows) = get_rows(&["3", "0 1 2", "3 4 5"]);
+
+            assert_eq!(
+                check_values_form_correct_rectangle(&size, &rows),
+                Err(ParseError::new(
+                    ParseErrorKind::WrongNumberOfRows {
+                        expected: 3,
+                        found: 2
+                    },
+                    3,
+                    1
+                ))
+            );
         }
 
         #[test]
-        #[should_panic]
-        fn panic_because_square_is_too_small() {
-            let size: usize = 2;
-            let tab = vec![0, 1, 2, 3];
-            check_values_form_correct_square(size, tab);
+        fn error_because_square_is_too_small() {
+            let (size, rows) = get_rows(&["1", "0"]);
+
+            assert_eq!(
+                check_values_form_correct_rectangle(&size, &rows),
+                Err(ParseError::new(ParseErrorKind::SizeTooSmall(1), 1, 1))
+            );
         }
 
         #[test]
-        fn no_panic() {
-            let size: usize = 3;
-            let tab = vec![0, 1, 2, 2, 3, 5, 8, 7, 6];
-            check_values_form_correct_square(size, tab);
+        fn error_because_board_is_too_large() {
+            let (size, rows) = get_rows(&["300 300", "0"]);
+
+            assert_eq!(
+                check_values_form_correct_rectangle(&size, &rows),
+                Err(ParseError::new(
+                    ParseErrorKind::SizeTooLarge {
+                        width: 300,
+                        height: 300
+                    },
+                    1,
+                    1
+                ))
+            );
+        }
+
+        #[test]
+        fn error_because_rectangle_is_too_flat() {
+            let (size, rows) = get_rows(&["3 1", "0 1 2"]);
+
+            assert_eq!(
+                check_values_form_correct_rectangle(&size, &rows),
+                Err(ParseError::new(ParseErrorKind::SizeTooSmall(1), 1, 3))
+            );
+        }
+
+        #[test]
+        fn error_because_wrong_number_of_lines_in_rectangle() {
+            let (size, rows) = get_rows(&["2 4", "0 1", "2 3", "4 5"]);
+
+            assert_eq!(
+                check_values_form_correct_rectangle(&size, &rows),
+                Err(ParseError::new(
+                    ParseErrorKind::WrongNumberOfRows {
+                        expected: 4,
+                        found: 3
+                    },
+                    4,
+                    1
+                ))
+            );
+        }
+
+        #[test]
+        fn no_error() {
+            let (size, rows) = get_rows(&["3", "0 1 2", "2 3 5", "8 7 6"]);
+
+            assert!(check_values_form_correct_rectangle(&size, &rows).is_ok());
+        }
+
+        #[test]
+        fn no_error_rectangle() {
+            let (size, rows) = get_rows(&["3 2", "0 1 2", "3 4 5"]);
+
+            assert!(check_values_form_correct_rectangle(&size, &rows).is_ok());
         }
     }
 
@@ -246,24 +787,74 @@ mod parser_tests {
 
         #[test]
         fn use_case_test() {
-            let data_string: Vec<String> = vec![
-                "3".to_string(),
-                "0  3  4".to_string(),
-                "1 5     6".to_string(),
-                "   2 7 8   ".to_string(),
+            let data_string: Vec<(usize, String)> = vec![
+                (1, "3".to_string()),
+                (2, "0  3  4".to_string()),
+                (3, "1 5     6".to_string()),
+                (4, "   2 7 8   ".to_string()),
+            ];
+            let (size, rows) = get_data(data_string).unwrap();
+            let values: Vec<Vec<usize>> = rows
+                .iter()
+                .map(|row| row.iter().map(|tile| tile.value).collect())
+                .collect();
+
+            assert_eq!(size.width.value, 3);
+            assert_eq!(size.height.value, 3);
+            assert_eq!(values, vec![vec![0, 3, 4], vec![1, 5, 6], vec![2, 7, 8]]);
+            assert_eq!(rows[2][0].column, 4);
+        }
+
+        #[test]
+        fn width_and_height() {
+            let data_string: Vec<(usize, String)> = vec![
+                (1, "3 2".to_string()),
+                (2, "0 1 2".to_string()),
+                (3, "3 4 5".to_string()),
             ];
-            let data_number: (usize, Vec<usize>) = (3, vec![0, 3, 4, 1, 5, 6, 2, 7, 8]);
+            let (header, rows) = get_data(data_string).unwrap();
+
+            assert_eq!(header.width.value, 3);
+            assert_eq!(header.height.value, 2);
+            assert_eq!(header.height.column, 3);
+            assert_eq!(rows.len(), 2);
+        }
+
+        #[test]
+        fn error_if_too_many_header_values() {
+            let data_string: Vec<(usize, String)> = vec![(1, "3 2 1".to_string())];
 
-            assert_eq!(get_data(data_string), data_number);
+            assert_eq!(
+                get_data(data_string),
+                Err(ParseError::new(ParseErrorKind::BadSizeLine, 1, 5))
+            );
         }
 
         #[test]
-        #[should_panic]
-        fn panic_if_cannot_get_size() {
-            let data_string: Vec<String> = vec![];
-            let data_number: (usize, Vec<usize>) = (3, vec![0, 3, 4, 1, 5, 6, 2, 7, 8]);
+        fn error_if_cannot_get_size() {
+            let data_string: Vec<(usize, String)> = vec![];
+
+            assert_eq!(
+                get_data(data_string),
+                Err(ParseError::new(ParseErrorKind::NoData, 1, 1))
+            );
+        }
+
+        #[test]
+        fn error_if_number_too_big() {
+            let data_string: Vec<(usize, String)> = vec![
+                (1, "3".to_string()),
+                (2, "1 99999999999999999999999".to_string()),
+            ];
 
-            assert_eq!(get_data(data_string), data_number);
+            assert_eq!(
+                get_data(data_string),
+                Err(ParseError::new(
+                    ParseErrorKind::InvalidNumber("99999999999999999999999".to_string()),
+                    2,
+                    3
+                ))
+            );
         }
     }
 }
@@ -0,0 +1,469 @@
+use crate::heuristic::{goal_positions, manhattan_distance, FromGoal, Heuristic};
+use crate::puzzle::Puzzle;
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/*
+ ** the tiles (blank excluded) are split in disjoint groups, each group gets the exact
+ ** number of moves of its own tiles needed to reach the goal, whatever the other tiles.
+ ** A move only ever displaces one tile, so the costs of the groups can be added.
+ ** A group of a single tile would only hold its manhattan distance : boards too large
+ ** for groups of two get no table at all and fall back to manhattan.
+ */
+const MAX_GROUP_SIZE: usize = 5;
+// a group of k tiles is searched over width * height ^ (k + 1) (tiles + blank) entries
+const MAX_SEARCH_STATES: usize = 1 << 24;
+const UNKNOWN: u8 = u8::MAX;
+const MAGIC: &[u8; 4] = b"NPDB";
+const VERSION: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub tiles: Vec<usize>,
+    costs: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternDatabase {
+    pub goal: Puzzle,
+    pub groups: Vec<Group>,
+    // for the manhattan fallback, when there is no group
+    goal_positions: Vec<(usize, usize)>,
+}
+
+fn group_size(len: usize) -> Option<usize> {
+    (2..=MAX_GROUP_SIZE).rev().find(|&size| {
+        len.checked_pow(size as u32 + 1)
+            .is_some_and(|states| states <= MAX_SEARCH_STATES)
+    })
+}
+
+pub fn partition(len: usize) -> Vec<Vec<usize>> {
+    let tiles: Vec<usize> = (1..len).collect();
+    let Some(size) = group_size(len) else {
+        return vec![];
+    };
+
+    tiles.chunks(size).map(|group| group.to_vec()).collect()
+}
+
+fn neighbours(cell: usize, width: usize, height: usize) -> Vec<usize> {
+    let (x, y) = (cell % width, cell / width);
+    let mut cells = vec![];
+
+    if y != 0 {
+        cells.push(cell - width);
+    }
+    if y != height - 1 {
+        cells.push(cell + width);
+    }
+    if x != 0 {
+        cells.push(cell - 1);
+    }
+    if x != width - 1 {
+        cells.push(cell + 1);
+    }
+
+    cells
+}
+
+fn encode(positions: &[usize], len: usize) -> usize {
+    positions
+        .iter()
+        .fold(0, |index, &position| index * len + position)
+}
+
+fn decode(mut index: usize, len: usize, positions: &mut [usize]) {
+    for position in positions.iter_mut().rev() {
+        *position = index % len;
+        index /= len;
+    }
+}
+
+/*
+ ** 0-1 breadth-first search backward from the goal over (group tiles, blank) :
+ ** moving the blank over a tile of the group costs 1, over any other tile costs 0.
+ ** States come out of the deque by increasing cost, so the first time a placement
+ ** of the group is reached is its cheapest, whatever the blank position.
+ */
+fn build_group(goal: &Puzzle, tiles: &[usize]) -> Vec<u8> {
+    let len = goal.data.len();
+    let mut costs = vec![UNKNOWN; len.pow(tiles.len() as u32)];
+    let mut distances = vec![UNKNOWN; costs.len() * len];
+    let mut positions: Vec<usize> = tiles
+        .iter()
+        .map(|&tile| goal.data.iter().position(|&value| value == tile).unwrap())
+        .collect();
+    let blank = goal.data.iter().position(|&value| value == 0).unwrap();
+
+    let start = encode(&positions, len) * len + blank;
+    distances[start] = 0;
+    let mut queue = VecDeque::from([(start, 0u8)]);
+    while let Some((index, cost)) = queue.pop_front() {
+        if cost > distances[index] {
+            continue;
+        }
+        let (pattern, blank) = (index / len, index % len);
+        if costs[pattern] == UNKNOWN {
+            costs[pattern] = cost;
+        }
+        decode(pattern, len, &mut positions);
+
+        for cell in neighbours(blank, goal.width, goal.height) {
+            match positions.iter().position(|&position| position == cell) {
+                Some(moved) => {
+                    let next_cost = cost.saturating_add(1).min(UNKNOWN - 1);
+                    positions[moved] = blank;
+                    let next = encode(&positions, len) * len + cell;
+                    positions[moved] = cell;
+                    if next_cost < distances[next] {
+                        distances[next] = next_cost;
+                        queue.push_back((next, next_cost));
+                    }
+                }
+                None => {
+                    let next = pattern * len + cell;
+                    if cost < distances[next] {
+                        distances[next] = cost;
+                        queue.push_front((next, cost));
+                    }
+                }
+            }
+        }
+    }
+
+    costs
+}
+
+/*
+ ** a stable hash so that different goals of the same size get different files
+ */
+fn fnv1a(data: &[usize]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &value| {
+        (hash ^ value as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buffer = [0u8; 1];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/*
+ ** $N_PUZZLE_PDB_DIR, or ./pattern_databases
+ */
+pub fn database_directory() -> PathBuf {
+    env::var_os("N_PUZZLE_PDB_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("pattern_databases"))
+}
+
+impl PatternDatabase {
+    pub fn build(goal: &Puzzle) -> PatternDatabase {
+        let groups = partition(goal.data.len())
+            .into_iter()
+            .map(|tiles| Group {
+                costs: build_group(goal, &tiles),
+                tiles,
+            })
+            .collect();
+
+        PatternDatabase {
+            goal: goal.clone(),
+            groups,
+            goal_positions: goal_positions(goal),
+        }
+    }
+
+    pub fn file_name(goal: &Puzzle) -> String {
+        format!(
+            "{}x{}-{:016x}.pdb",
+            goal.width,
+            goal.height,
+            fnv1a(&goal.data)
+        )
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(self.goal.width as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.goal.height as u32).to_le_bytes());
+        for &value in &self.goal.data {
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.groups.len() as u32).to_le_bytes());
+        for group in &self.groups {
+            bytes.extend_from_slice(&(group.tiles.len() as u32).to_le_bytes());
+            for &tile in &group.tiles {
+                bytes.extend_from_slice(&(tile as u32).to_le_bytes());
+            }
+            bytes.extend_from_slice(&group.costs);
+        }
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::File::create(path)?.write_all(&bytes)
+    }
+
+    /*
+     ** fails if the file was built for another goal or another partition
+     */
+    pub fn load(path: &Path, goal: &Puzzle) -> io::Result<PatternDatabase> {
+        let mut reader = io::BufReader::new(fs::File::open(path)?);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_u8(&mut reader)? != VERSION {
+            return Err(invalid("not a pattern database"));
+        }
+        let width = read_u32(&mut reader)? as usize;
+        let height = read_u32(&mut reader)? as usize;
+        if (width, height) != (goal.width, goal.height) {
+            return Err(invalid("pattern database built for another size"));
+        }
+        for &value in &goal.data {
+            if read_u32(&mut reader)? as usize != value {
+                return Err(invalid("pattern database built for another goal"));
+            }
+        }
+
+        let len = goal.data.len();
+        let expected = partition(len);
+        if read_u32(&mut reader)? as usize != expected.len() {
+            return Err(invalid("pattern database built with another partition"));
+        }
+        let mut groups = vec![];
+        for tiles in expected {
+            let stored = (0..read_u32(&mut reader)?)
+                .map(|_| read_u32(&mut reader))
+                .collect::<io::Result<Vec<u32>>>()?;
+            if stored
+                .iter()
+                .map(|&tile| tile as usize)
+                .ne(tiles.iter().copied())
+            {
+                return Err(invalid("pattern database built with another partition"));
+            }
+            let mut costs = vec![0u8; len.pow(tiles.len() as u32)];
+            reader.read_exact(&mut costs)?;
+            groups.push(Group { tiles, costs });
+        }
+
+        Ok(PatternDatabase {
+            goal: goal.clone(),
+            groups,
+            goal_positions: goal_positions(goal),
+        })
+    }
+
+    pub fn load_or_build(goal: &Puzzle, directory: &Path) -> PatternDatabase {
+        let path = directory.join(PatternDatabase::file_name(goal));
+
+        PatternDatabase::load(&path, goal).unwrap_or_else(|_| {
+            let database = PatternDatabase::build(goal);
+            // the file is only a cache, the search goes on without it
+            let _ = database.save(&path);
+            database
+        })
+    }
+}
+
+impl FromGoal for PatternDatabase {
+    /*
+     ** read from the database directory, or built and written there for the next runs
+     */
+    fn new(goal: &Puzzle) -> PatternDatabase {
+        PatternDatabase::load_or_build(goal, &database_directory())
+    }
+}
+
+impl Heuristic for PatternDatabase {
+    fn name(&self) -> &str {
+        "pdb"
+    }
+
+    fn goal(&self) -> &Puzzle {
+        &self.goal
+    }
+
+    // kept in memory only, every start would otherwise leave its own file behind
+    fn toward(&self, goal: &Puzzle) -> Option<Box<dyn Heuristic>> {
+        Some(Box::new(PatternDatabase::build(goal)))
+    }
+
+    fn evaluate(&self, puzzle: &Puzzle) -> usize {
+        if self.groups.is_empty() {
+            return manhattan_distance(puzzle, &self.goal_positions);
+        }
+        let len = puzzle.data.len();
+        let mut positions = vec![0; len];
+        for (position, &value) in puzzle.data.iter().enumerate() {
+            positions[value] = position;
+        }
+
+        self.groups
+            .iter()
+            .map(|group| {
+                let index = group
+                    .tiles
+                    .iter()
+                    .fold(0, |index, &tile| index * len + positions[tile]);
+                group.costs[index] as usize
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod pattern_database_tests {
+    use crate::node::Node;
+    use crate::puzzle::Puzzle;
+    use std::collections::{HashMap, VecDeque};
+
+    fn true_distances(goal: &Puzzle) -> HashMap<Puzzle, usize> {
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::from([goal.clone()]);
+        distances.insert(goal.clone(), 0);
+        while let Some(puzzle) = queue.pop_front() {
+            let distance = distances[&puzzle];
+            for next in Node::calculate_next_states(&puzzle) {
+                if !distances.contains_key(&next) {
+                    distances.insert(next.clone(), distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    mod partition {
+        use crate::pattern_database::partition;
+
+        #[test]
+        fn fifteen_puzzle_is_five_five_five() {
+            let sizes: Vec<usize> = partition(16).iter().map(|group| group.len()).collect();
+
+            assert_eq!(sizes, vec![5, 5, 5]);
+        }
+
+        #[test]
+        fn eight_puzzle_is_five_three() {
+            assert_eq!(partition(9), vec![vec![1, 2, 3, 4, 5], vec![6, 7, 8]]);
+        }
+
+        #[test]
+        fn bigger_boards_get_smaller_groups() {
+            assert!(partition(25).iter().all(|group| group.len() == 4));
+            assert_eq!(partition(25).concat(), (1..25).collect::<Vec<usize>>());
+        }
+
+        #[test]
+        fn no_group_past_sixteen_by_sixteen() {
+            assert_eq!(partition(256)[0].len(), 2);
+            assert!(partition(17 * 17).is_empty());
+        }
+    }
+
+    mod evaluate {
+        use super::true_distances;
+        use crate::generator::{self, Method};
+        use crate::heuristic::{FromGoal, Heuristic, Manhattan};
+        use crate::pattern_database::PatternDatabase;
+        use crate::puzzle::Puzzle;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        #[test]
+        fn exact_with_a_single_group() {
+            let goal = Puzzle::get_final_state(3, 2);
+            let database = PatternDatabase::build(&goal);
+
+            assert_eq!(database.groups.len(), 1);
+            for (puzzle, distance) in true_distances(&goal) {
+                assert_eq!(database.evaluate(&puzzle), distance, "{}", puzzle);
+            }
+        }
+
+        #[test]
+        fn manhattan_without_groups() {
+            let goal = Puzzle::get_final_state(17, 17);
+            let database = PatternDatabase::build(&goal);
+            let manhattan = Manhattan::new(&goal);
+            let mut rng = StdRng::seed_from_u64(17);
+            let puzzle = generator::generate(&mut rng, &goal, Method::Walk(40), true);
+
+            assert!(database.groups.is_empty());
+            assert_eq!(database.evaluate(&puzzle), manhattan.evaluate(&puzzle));
+        }
+
+        #[test]
+        fn admissible_and_at_least_manhattan() {
+            let goal = Puzzle::get_final_state(4, 2);
+            let database = PatternDatabase::build(&goal);
+            let manhattan = Manhattan::new(&goal);
+
+            assert_eq!(database.groups.len(), 2);
+            for (puzzle, distance) in true_distances(&goal) {
+                let cost = database.evaluate(&puzzle);
+                assert!(cost <= distance, "{}", puzzle);
+                assert!(cost >= manhattan.evaluate(&puzzle));
+            }
+        }
+    }
+
+    mod save {
+        use crate::goal::Goal;
+        use crate::pattern_database::PatternDatabase;
+        use crate::puzzle::Puzzle;
+        use std::env;
+        use std::fs;
+
+        #[test]
+        fn round_trip() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let database = PatternDatabase::build(&goal);
+            let directory = env::temp_dir().join(format!("n-puzzle-pdb-{}", std::process::id()));
+            let path = directory.join(PatternDatabase::file_name(&goal));
+
+            database.save(&path).unwrap();
+            assert_eq!(PatternDatabase::load(&path, &goal).unwrap(), database);
+            assert_eq!(PatternDatabase::load_or_build(&goal, &directory), database);
+
+            let other_goal = Goal::RowMajor.state(3, 3).unwrap();
+            assert!(PatternDatabase::load(&path, &other_goal).is_err());
+            fs::remove_dir_all(directory).unwrap();
+        }
+
+        // tiles past 255
+        #[test]
+        fn round_trip_seventeen_by_seventeen() {
+            let goal = Puzzle::get_final_state(17, 17);
+            let database = PatternDatabase::build(&goal);
+            let directory = env::temp_dir().join(format!("n-puzzle-pdb-17-{}", std::process::id()));
+            let path = directory.join(PatternDatabase::file_name(&goal));
+
+            database.save(&path).unwrap();
+            assert_eq!(PatternDatabase::load(&path, &goal).unwrap(), database);
+            let other_goal = Goal::RowMajor.state(17, 17).unwrap();
+            assert!(PatternDatabase::load(&path, &other_goal).is_err());
+            fs::remove_dir_all(directory).unwrap();
+        }
+    }
+}
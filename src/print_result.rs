@@ -1,204 +1,147 @@
-use crate::graph::Graph;
-use crate::node::Node;
-use std::time::SystemTime;
-
-pub fn print_data(graph: Graph, final_node: Node, start_time: SystemTime) {
-    println!("Number of moves : {}", final_node.distance);
-    println!("Time elapsed : {:?}", start_time.elapsed().unwrap());
-    println!("Open list size : {}", graph.open_list.len());
-    println!("Closed list size : {}", graph.closed_list.len());
-    println!(
-        "Total number of states ever represented (closed + open) : {}",
-        graph.closed_list.len() + graph.open_list.len()
-    );
-    println!(
-        "Maximum number of states represented in open list : {}",
-        graph.max_states
-    );
-}
-
-pub fn print_solution_with_retrieving(final_node: Node, graph: Graph) {
-    println!("Solution sequence :");
-    print!("{}", recursive_path(&final_node, graph));
+use n_puzzle::parser::ParseError;
+use n_puzzle::verify::Replay;
+use n_puzzle::{Improvement, Puzzle, Solution, Statistics};
+use std::path::Path;
+
+pub fn print_data(solution: &Solution) {
+    println!("Number of moves : {}", solution.len());
+    if solution.statistics.stopped.is_some() {
+        println!("Stopped early, this path may not be the shortest one");
+    }
+    print_statistics(&solution.statistics);
 }
 
-fn recursive_path(curr_node: &Node, graph: Graph) -> String {
-    let str = if curr_node.distance > 0 {
-        match select_previous_node(curr_node.clone(), graph.clone()) {
-            Ok(prev_node) => recursive_path(&prev_node, graph),
-            Err(str) => str,
-        }
+/*
+ ** also shown on its own when the search was stopped before the goal
+ */
+pub fn print_statistics(statistics: &Statistics) {
+    println!("Time elapsed : {:?}", statistics.time_elapsed);
+    if statistics.thresholds.is_empty() {
+        println!("Open list size : {}", statistics.open_states);
+        println!("Closed list size : {}", statistics.closed_states);
+        println!(
+            "Total number of states ever represented (closed + open) : {}",
+            statistics.total_states
+        );
+        println!(
+            "Maximum number of states represented in open list : {}",
+            statistics.max_states
+        );
     } else {
-        String::from("")
-    };
-    str + &curr_node.state.to_string() + "\n"
-}
-
-fn select_previous_node(curr_node: Node, graph: Graph) -> Result<Node, String> {
-    //println!("{}", curr_node.clone().distance);
-    let mut childs = Node::calculate_next_nodes(curr_node.clone(), graph.final_node, |_x, _y| 0);
-    while !childs.is_empty() {
-        let child = childs.pop().unwrap();
-        //println!("{}", child.clone());
-        for open in graph.open_list.clone() {
-            if open.distance == curr_node.distance - 1 && open.state == child.clone().state {
-                return Ok(open.clone());
-            }
-        }
-
-        for closed in graph.closed_list.clone() {
-            if closed.distance == curr_node.distance - 1 && closed.state == child.state {
-                return Ok(closed.clone());
-            }
+        // iterative deepening keeps only the current path, no open or closed list
+        println!("Path length : {}", statistics.open_states);
+        println!("Expanded states : {}", statistics.closed_states);
+        println!(
+            "Total number of states ever represented (expanded + path) : {}",
+            statistics.total_states
+        );
+        println!("Longest path represented : {}", statistics.max_states);
+        println!("Number of iterations : {}", statistics.thresholds.len());
+        println!(
+            "Threshold per iteration : {}",
+            statistics
+                .thresholds
+                .iter()
+                .map(|threshold| threshold.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+    if !statistics.improvements.is_empty() {
+        println!(
+            "Number of improved solutions : {}",
+            statistics.improvements.len()
+        );
+    }
+    for part in &statistics.parts {
+        println!("Expanded states ({}) : {}", part.name, part.expanded_states);
+    }
+    if statistics.stopped.is_some() {
+        println!("Highest f reached : {}", statistics.progress.f_bound);
+        if let Some(best_h_score) = statistics.progress.best_h_score {
+            println!("Lowest h reached : {}", best_h_score);
         }
     }
-
-    Err("".to_string())
 }
 
-#[cfg(test)]
-mod print_result {
-    mod select_previous_node {
-        use crate::graph::Graph;
-        use crate::node::*;
-        use crate::print_result::*;
-        use crate::puzzle::*;
-
-        #[test]
-        fn normal_use_case() {
-            let data1 = vec![1, 2, 3, 8, 0, 4, 7, 6, 5];
-            let node1 = Node {
-                state: Puzzle {
-                    data: data1,
-                    size: 3,
-                },
-                f_score: 1,
-                distance: 1,
-            };
-            let data3 = vec![1, 2, 3, 8, 4, 5, 7, 6, 0];
-            let node3 = Node {
-                state: Puzzle {
-                    data: data3,
-                    size: 3,
-                },
-                f_score: 1,
-                distance: 3,
-            };
-            let data2 = vec![1, 2, 3, 8, 4, 0, 7, 6, 5];
-            let node2 = Node {
-                state: Puzzle {
-                    data: data2,
-                    size: 3,
-                },
-                f_score: 1,
-                distance: 2,
-            };
-
-            let graph = Graph {
-                open_list: vec![node1.clone(), node2.clone(), node3.clone()],
-                closed_list: vec![],
-                start_node: node3.clone(),
-                final_node: node1.clone(),
-                heuristic: |_a, _b| 0,
-                max_states: 0,
-            };
-
-            assert_eq!(select_previous_node(node2, graph), Ok(node1));
-        }
+pub fn print_replay(replay: &Replay) {
+    println!("Moves played : {}", replay.moves);
+    if let Some((position, reason)) = &replay.illegal {
+        println!("Illegal move {} : {}", position, reason);
+    } else if !replay.reaches_goal {
+        println!("The goal isn't reached, the moves end on :");
+        println!("{}", replay.final_state);
+    }
+    println!(
+        "Reaches the goal : {}",
+        if replay.reaches_goal { "yes" } else { "no" }
+    );
+}
 
-        #[test]
-        fn dead_end() {
-            let data1 = vec![1, 2, 3, 8, 0, 4, 7, 6, 5];
-            let node1 = Node {
-                state: Puzzle {
-                    data: data1,
-                    size: 3,
-                },
-                f_score: 1,
-                distance: 2,
-            };
-            let start_node = Node {
-                state: Puzzle {
-                    data: vec![1, 2, 3, 4, 5, 6, 7, 8, 0],
-                    size: 0,
-                },
-                f_score: 0,
-                distance: 0,
-            };
+pub fn print_optimality(moves: usize, shortest: usize) {
+    println!("Shortest solution : {} moves", shortest);
+    // fewer only when the heuristic overestimates
+    if moves <= shortest {
+        println!("Optimal : yes");
+    } else {
+        println!("Optimal : no, {} moves too many", moves - shortest);
+    }
+}
 
-            let graph = Graph {
-                open_list: vec![node1.clone()],
-                closed_list: vec![],
-                start_node,
-                final_node: node1.clone(),
-                heuristic: |_a, _b| 0,
-                max_states: 0,
-            };
+pub fn print_improvement(improvement: &Improvement) {
+    println!(
+        "Found {} moves, at most {:.2} times the shortest path, after {:?}",
+        improvement.moves, improvement.weight, improvement.time_elapsed
+    );
+}
 
-            assert_eq!(select_previous_node(node1, graph), Err("".to_string()));
-        }
+pub fn print_solution(path: &[Puzzle]) {
+    println!("Solution sequence :");
+    for state in path {
+        println!("{}", state);
     }
+}
 
-    mod recursive_path {
-        use crate::graph::Graph;
-        use crate::node::*;
-        use crate::print_result::*;
-        use crate::puzzle::*;
-
-        #[test]
-        fn print_small_solution() {
-            let data1 = vec![1, 2, 3, 8, 0, 4, 7, 6, 5];
-            let node1 = Node {
-                state: Puzzle {
-                    data: data1,
-                    size: 3,
-                },
-                f_score: 1,
-                distance: 0,
-            };
-            let data2 = vec![1, 2, 3, 8, 4, 0, 7, 6, 5];
-            let node2 = Node {
-                state: Puzzle {
-                    data: data2,
-                    size: 3,
-                },
-                f_score: 1,
-                distance: 1,
-            };
-            let data3 = vec![1, 2, 3, 8, 4, 5, 7, 6, 0];
-            let node3 = Node {
-                state: Puzzle {
-                    data: data3,
-                    size: 3,
-                },
-                f_score: 1,
-                distance: 2,
-            };
+pub fn print_moves(solution: &Solution) {
+    println!("Solution moves (direction of the blank) :");
+    println!("{}", solution.move_string());
+}
 
-            let graph = Graph {
-                open_list: vec![node1.clone(), node2.clone(), node3.clone()],
-                closed_list: vec![],
-                start_node: node3.clone(),
-                final_node: node1,
-                heuristic: |_a, _b| 0,
-                max_states: 0,
-            };
+pub fn print_tiles(solution: &Solution) {
+    println!("Solution tiles (slid into the blank) :");
+    println!(
+        "{}",
+        solution
+            .tiles()
+            .iter()
+            .map(|tile| tile.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    );
+}
 
-            assert_eq!(
-                recursive_path(&node3, graph),
-                "1  2  3  \n\
-                 8  0  4  \n\
-                 7  6  5  \n\
-                 \n\
-                 1  2  3  \n\
-                 8  4  0  \n\
-                 7  6  5  \n\
-                 \n\
-                 1  2  3  \n\
-                 8  4  5  \n\
-                 7  6  0  \n\
-                 \n"
-            );
-        }
+/*
+ ** error: duplicate tile 4
+ **  --> puzzle.txt:3:5
+ **   |
+ ** 3 | 5 6 4 4
+ **   |     ^
+ */
+pub fn print_parse_error(filename: &Path, lines: &[String], error: &ParseError) {
+    let number = error.line.to_string();
+    let gutter = " ".repeat(number.len());
+
+    eprintln!("error: {}", error.kind);
+    eprintln!(
+        "{}--> {}:{}:{}",
+        gutter,
+        filename.display(),
+        error.line,
+        error.column
+    );
+    if let Some(line) = lines.get(error.line - 1) {
+        eprintln!("{} |", gutter);
+        eprintln!("{} | {}", number, line);
+        eprintln!("{} | {}^", gutter, " ".repeat(error.column - 1));
     }
 }
@@ -1,19 +1,22 @@
 extern crate rand;
-use self::rand::Rng;
+use crate::generator;
+use crate::generator::Method;
 use std::cmp;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 #[derive(Debug, Clone, Eq)]
 pub struct Puzzle {
     pub data: Vec<usize>,
-    pub size: usize,
+    pub width: usize,
+    pub height: usize,
 }
 
 impl fmt::Display for Puzzle {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let padding = if self.size < 11 { 3 } else { 4 };
+        let padding = if self.data.len() <= 100 { 3 } else { 4 };
         for i in 0..self.data.len() {
-            if i % self.size == 0 && i != 0 {
+            if i % self.width == 0 && i != 0 {
                 writeln!(f)?;
             }
             write!(f, "{0:<1$}", self.data[i], padding)?;
@@ -29,60 +32,57 @@ impl cmp::PartialEq for Puzzle {
     }
 }
 
+impl Hash for Puzzle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
 impl Puzzle {
     pub fn get_value(&self, x: usize, y: usize) -> usize {
-        self.data[y * self.size + x]
+        self.data[y * self.width + x]
     }
 
     pub fn get_position(&self, value: usize) -> (usize, usize) {
-        let size = self.size;
-        for pos in 0..(size * size) {
+        let width = self.width;
+        for pos in 0..self.data.len() {
             if self.data[pos] == value {
-                return (pos % size, pos / size);
+                return (pos % width, pos / width);
             }
         }
         panic!("value not found inside the puzzle");
     }
 
     pub fn set_value(&mut self, x: usize, y: usize, value: usize) {
-        self.data[y * self.size + x] = value;
+        self.data[y * self.width + x] = value;
     }
 
-    pub fn new(size: usize) -> Puzzle {
-        if size < 2 {
-            panic!("Size should be higher than 1")
-        }
-
-        let mut all_the_values: Vec<usize> = (0..(size * size)).collect();
-        let mut data = vec![0usize; size * size];
-        data.iter_mut().for_each(|value| {
-            while {
-                *value = all_the_values
-                    [rand::thread_rng().gen_range(0, all_the_values.clone().iter().count())];
-                !all_the_values.contains(value)
-            } {}
-            all_the_values.remove(
-                all_the_values
-                    .clone()
-                    .iter()
-                    .position(|&x| x == *value)
-                    .unwrap(),
-            );
-        });
-        let puzzle = Puzzle { data, size };
-        if Puzzle::is_solvable(puzzle.clone()) {
-            puzzle
-        } else {
-            Puzzle::new(size)
+    /*
+     ** uniformly random among the boards solvable toward the goal
+     */
+    pub fn new(width: usize, height: usize, goal_puzzle: &Puzzle) -> Puzzle {
+        if width < 2 || height < 2 {
+            panic!("Width and height should be higher than 1")
         }
+
+        generator::generate(&mut rand::thread_rng(), goal_puzzle, Method::Uniform, true)
     }
 
-    pub fn new_from_file(data: Vec<usize>, size: usize) -> Puzzle {
-        let puzzle = Puzzle { data, size };
-        if !Puzzle::is_solvable(puzzle.clone()) {
-            panic!("Unsolvable puzzle\n");
+    pub fn new_from_file(
+        data: Vec<usize>,
+        width: usize,
+        height: usize,
+        goal_puzzle: &Puzzle,
+    ) -> Option<Puzzle> {
+        let puzzle = Puzzle {
+            data,
+            width,
+            height,
+        };
+        if Puzzle::is_solvable(puzzle.clone(), goal_puzzle) {
+            Some(puzzle)
         } else {
-            puzzle
+            None
         }
     }
 
@@ -105,74 +105,75 @@ impl Puzzle {
         sort_count
     }
 
-    pub fn is_solvable(puzzle: Puzzle) -> bool {
-        let size = puzzle.size;
-        let goal_puzzle = Puzzle::get_final_state(size);
+    /*
+     ** a horizontal move never changes the number of inversions, a vertical one
+     ** jumps a tile over width - 1 others : with an odd width the parity of the
+     ** inversions is invariant, with an even width it flips along with the row
+     ** of the blank, so the blank row is added in that case. The height of the
+     ** board plays no part.
+     */
+    pub fn is_solvable(puzzle: Puzzle, goal_puzzle: &Puzzle) -> bool {
+        let width = puzzle.width;
         let mut start_inversion = Puzzle::inversion(puzzle.clone());
         let mut goal_inversion = Puzzle::inversion(goal_puzzle.clone());
-        if size % 2 == 0 {
-            let (mut x1, mut y1) = (0, 0);
-            let (mut x2, mut y2) = (0, 0);
-
-            for i in 0..size {
-                for j in 0..size {
-                    if puzzle.get_value(j, i) == 0 {
-                        x1 = j;
-                        y1 = i;
-                    }
-                    if goal_puzzle.get_value(j, i) == 0 {
-                        x2 = j;
-                        y2 = i;
-                    }
-                }
-            }
-            start_inversion += (y1 * size + x1) / size;
-            goal_inversion += (y2 * size + x2) / size;
-            //return start_inversion % 2 != goal_inversion % 2;
+        if width.is_multiple_of(2) {
+            start_inversion += puzzle.get_position(0).1;
+            goal_inversion += goal_puzzle.get_position(0).1;
         }
         start_inversion % 2 == goal_inversion % 2
     }
 
-    pub fn get_final_state(size: usize) -> Puzzle {
-        let data = vec![0usize; size * size];
-        let mut puzzle = Puzzle { data, size };
+    pub fn get_final_state(width: usize, height: usize) -> Puzzle {
+        let data = vec![0usize; width * height];
+        let mut puzzle = Puzzle {
+            data,
+            width,
+            height,
+        };
 
+        // the spiral ends on the blank : the last value wraps around to 0
+        let len = width * height;
         let mut current_number = 1;
         let mut min_x = 0;
         let mut min_y = 0;
-        let mut max_x = size - 1;
-        let mut max_y = size - 1;
-
+        let mut max_x = width - 1;
+        let mut max_y = height - 1;
         loop {
             for right in min_x..=max_x {
-                puzzle.set_value(right, min_x, current_number);
+                puzzle.set_value(right, min_y, current_number % len);
                 current_number += 1;
             }
+            if current_number > len {
+                break;
+            }
             min_y += 1;
+
             for down in min_y..=max_y {
-                puzzle.set_value(max_x, down, current_number);
+                puzzle.set_value(max_x, down, current_number % len);
                 current_number += 1;
             }
-            max_x -= 1;
-            if current_number == size * size {
-                puzzle.set_value(max_x, max_y, 0);
+            if current_number > len {
                 break;
             }
+            max_x -= 1;
 
             for left in (min_x..=max_x).rev() {
-                puzzle.set_value(left, max_y, current_number);
+                puzzle.set_value(left, max_y, current_number % len);
                 current_number += 1;
             }
+            if current_number > len {
+                break;
+            }
             max_y -= 1;
+
             for up in (min_y..=max_y).rev() {
-                puzzle.set_value(min_x, up, current_number);
+                puzzle.set_value(min_x, up, current_number % len);
                 current_number += 1;
             }
-            min_x += 1;
-            if current_number == size * size {
-                puzzle.set_value(max_x, max_y, 0);
+            if current_number > len {
                 break;
             }
+            min_x += 1;
         }
         puzzle
     }
@@ -187,7 +188,8 @@ mod puzzle_tests {
         fn get_0() {
             let puzzle = Puzzle {
                 data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
 
             assert_eq!(puzzle.get_value(1, 1), 0);
@@ -197,7 +199,8 @@ mod puzzle_tests {
         fn get_7() {
             let puzzle = Puzzle {
                 data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
 
             assert_eq!(puzzle.get_value(0, 2), 7);
@@ -207,7 +210,8 @@ mod puzzle_tests {
         fn get_15() {
             let puzzle = Puzzle {
                 data: vec![1, 2, 3, 9, 8, 0, 4, 10, 7, 6, 5, 11, 12, 13, 14, 15],
-                size: 4,
+                width: 4,
+                height: 4,
             };
 
             assert_eq!(puzzle.get_value(3, 3), 15);
@@ -218,7 +222,8 @@ mod puzzle_tests {
         fn get_out_of_bounds() {
             let puzzle = Puzzle {
                 data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
 
             puzzle.get_value(42, 42);
@@ -232,7 +237,8 @@ mod puzzle_tests {
         fn get_0() {
             let puzzle = Puzzle {
                 data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
 
             assert_eq!(puzzle.get_position(0), (1, 1));
@@ -242,7 +248,8 @@ mod puzzle_tests {
         fn get_7() {
             let puzzle = Puzzle {
                 data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
 
             assert_eq!(puzzle.get_position(7), (0, 2));
@@ -252,7 +259,8 @@ mod puzzle_tests {
         fn get_15() {
             let puzzle = Puzzle {
                 data: vec![1, 2, 3, 9, 8, 0, 4, 10, 7, 6, 5, 11, 12, 13, 14, 15],
-                size: 4,
+                width: 4,
+                height: 4,
             };
 
             assert_eq!(puzzle.get_position(15), (3, 3));
@@ -263,7 +271,8 @@ mod puzzle_tests {
         fn get_impossible() {
             let puzzle = Puzzle {
                 data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
 
             puzzle.get_position(9);
@@ -277,11 +286,13 @@ mod puzzle_tests {
         fn change_0_to_9() {
             let mut puzzle = Puzzle {
                 data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
             let changed = Puzzle {
                 data: vec![1, 2, 3, 8, 9, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
 
             puzzle.set_value(1, 1, 9);
@@ -292,11 +303,13 @@ mod puzzle_tests {
         fn change_15_to_16() {
             let mut puzzle = Puzzle {
                 data: vec![1, 2, 3, 9, 8, 0, 4, 10, 7, 6, 5, 11, 12, 13, 14, 15],
-                size: 4,
+                width: 4,
+                height: 4,
             };
             let changed = Puzzle {
                 data: vec![1, 2, 3, 9, 8, 0, 4, 10, 7, 6, 5, 11, 12, 13, 14, 16],
-                size: 4,
+                width: 4,
+                height: 4,
             };
 
             puzzle.set_value(3, 3, 16);
@@ -308,7 +321,8 @@ mod puzzle_tests {
         fn change_out_of_bounds() {
             let mut puzzle = Puzzle {
                 data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
 
             puzzle.set_value(42, 42, 9);
@@ -323,9 +337,10 @@ mod puzzle_tests {
             let size = 3;
             let expected_final_state = Puzzle {
                 data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size,
+                width: size,
+                height: size,
             };
-            let result_data = Puzzle::get_final_state(size);
+            let result_data = Puzzle::get_final_state(size, size);
 
             assert_eq!(result_data, expected_final_state);
         }
@@ -343,9 +358,10 @@ mod puzzle_tests {
                     1, 2, 3, 4, 5, 16, 17, 18, 19, 6, 15, 24, 0, 20, 7, 14, 23, 22, 21, 8, 13, 12,
                     11, 10, 9,
                 ],
-                size,
+                width: size,
+                height: size,
             };
-            let result_data = Puzzle::get_final_state(size);
+            let result_data = Puzzle::get_final_state(size, size);
 
             assert_eq!(result_data, expected_final_state);
         }
@@ -368,9 +384,10 @@ mod puzzle_tests {
                     81, 94, 93, 92, 91, 74, 49, 16, 30, 59, 80, 79, 78, 77, 76, 75, 50, 17, 29, 58,
                     57, 56, 55, 54, 53, 52, 51, 18, 28, 27, 26, 25, 24, 23, 22, 21, 20, 19,
                 ],
-                size,
+                width: size,
+                height: size,
             };
-            let result_data = Puzzle::get_final_state(size);
+            let result_data = Puzzle::get_final_state(size, size);
 
             assert_eq!(result_data, expected_final_state);
         }
@@ -387,6 +404,27 @@ mod puzzle_tests {
         28 27 26 25 24 23 22 21 20 19
         */
 
+        #[test]
+        fn wider_than_tall() {
+            let expected_final_state = Puzzle {
+                data: vec![1, 2, 3, 4, 10, 11, 0, 5, 9, 8, 7, 6],
+                width: 4,
+                height: 3,
+            };
+
+            assert_eq!(Puzzle::get_final_state(4, 3), expected_final_state);
+        }
+
+        #[test]
+        fn taller_than_wide() {
+            let expected_final_state = Puzzle {
+                data: vec![1, 2, 3, 10, 11, 4, 9, 0, 5, 8, 7, 6],
+                width: 3,
+                height: 4,
+            };
+
+            assert_eq!(Puzzle::get_final_state(3, 4), expected_final_state);
+        }
     }
     mod partial_eq {
         use crate::puzzle::*;
@@ -396,10 +434,18 @@ mod puzzle_tests {
             let size = 3;
 
             let data = vec![0, 1, 2, 3, 4, 5, 6, 8, 7];
-            let puzzle = Puzzle { data, size };
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
 
             let data2 = vec![0, 1, 2, 3, 4, 5, 6, 8, 7];
-            let puzzle2 = Puzzle { data: data2, size };
+            let puzzle2 = Puzzle {
+                data: data2,
+                width: size,
+                height: size,
+            };
 
             assert_eq!(puzzle, puzzle2);
         }
@@ -409,10 +455,18 @@ mod puzzle_tests {
             let size = 3;
 
             let data = vec![0, 1, 2, 3, 4, 5, 6, 8, 7];
-            let puzzle = Puzzle { data, size };
+            let puzzle = Puzzle {
+                data,
+                width: size,
+                height: size,
+            };
 
             let data2 = vec![0, 2, 1, 3, 4, 5, 6, 8, 7];
-            let puzzle2 = Puzzle { data: data2, size };
+            let puzzle2 = Puzzle {
+                data: data2,
+                width: size,
+                height: size,
+            };
             assert_ne!(puzzle, puzzle2);
         }
     }
@@ -423,66 +477,178 @@ mod puzzle_tests {
         #[test]
         #[should_panic]
         fn panic_because_size_less_than_two() {
-            Puzzle::new(1);
+            Puzzle::new(
+                1,
+                1,
+                &Puzzle {
+                    data: vec![0],
+                    width: 1,
+                    height: 1,
+                },
+            );
         }
 
         #[test]
         fn correct_puzzle_of_size_three() {
-            assert_eq!(Puzzle::new(3).size, 3);
+            assert_eq!(Puzzle::new(3, 3, &Puzzle::get_final_state(3, 3)).width, 3);
         }
 
         #[test]
         fn correct_puzzle_of_size_ten() {
-            assert_eq!(Puzzle::new(10).size, 10);
+            assert_eq!(
+                Puzzle::new(10, 10, &Puzzle::get_final_state(10, 10)).width,
+                10
+            );
+        }
+
+        #[test]
+        fn correct_rectangular_puzzle() {
+            let puzzle = Puzzle::new(3, 5, &Puzzle::get_final_state(3, 5));
+
+            assert_eq!((puzzle.width, puzzle.height), (3, 5));
+            assert_eq!(puzzle.data.len(), 15);
         }
     }
 
     mod is_solvable {
+        use crate::node::Node;
         use crate::puzzle::*;
+        use std::collections::{HashSet, VecDeque};
 
         #[test]
         fn solvable_puzzle() {
             let puzzle = Puzzle {
                 data: vec![0, 1, 2, 3, 4, 5, 6, 8, 7],
-                size: 3,
+                width: 3,
+                height: 3,
             };
-            assert!(Puzzle::is_solvable(puzzle));
+            assert!(Puzzle::is_solvable(puzzle, &Puzzle::get_final_state(3, 3)));
         }
 
         #[test]
         fn solvable_puzzle_already_solved() {
             let puzzle = Puzzle {
                 data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
-            assert!(Puzzle::is_solvable(puzzle));
+            assert!(Puzzle::is_solvable(puzzle, &Puzzle::get_final_state(3, 3)));
         }
 
         #[test]
         fn solvable_puzzle_with_one_move() {
             let puzzle = Puzzle {
                 data: vec![1, 2, 3, 0, 8, 4, 7, 6, 5],
-                size: 3,
+                width: 3,
+                height: 3,
             };
-            assert!(Puzzle::is_solvable(puzzle));
+            assert!(Puzzle::is_solvable(puzzle, &Puzzle::get_final_state(3, 3)));
         }
 
         #[test]
         fn solvable_puzzle_with_fifteen_moves() {
             let puzzle = Puzzle {
                 data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
-                size: 3,
+                width: 3,
+                height: 3,
             };
-            assert!(Puzzle::is_solvable(puzzle));
+            assert!(Puzzle::is_solvable(puzzle, &Puzzle::get_final_state(3, 3)));
         }
 
         #[test]
         fn unsolvable_puzzle() {
             let puzzle = Puzzle {
                 data: vec![0, 1, 2, 3, 4, 5, 6, 7, 8],
-                size: 3,
+                width: 3,
+                height: 3,
+            };
+            assert!(!Puzzle::is_solvable(puzzle, &Puzzle::get_final_state(3, 3)));
+        }
+
+        #[test]
+        fn solvable_to_row_major_goal() {
+            let puzzle = Puzzle {
+                data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0, 15],
+                width: 4,
+                height: 4,
+            };
+            let goal = Puzzle {
+                data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0],
+                width: 4,
+                height: 4,
             };
-            assert_eq!(Puzzle::is_solvable(puzzle), false);
+            assert!(Puzzle::is_solvable(puzzle, &goal));
+        }
+
+        #[test]
+        fn unsolvable_to_row_major_goal() {
+            let puzzle = Puzzle {
+                data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 15, 14, 0],
+                width: 4,
+                height: 4,
+            };
+            let goal = Puzzle {
+                data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0],
+                width: 4,
+                height: 4,
+            };
+            assert!(!Puzzle::is_solvable(puzzle, &goal));
+        }
+
+        fn check_every_permutation(width: usize, height: usize) {
+            // every state reachable from the goal is solvable, exactly half of them are
+            let goal = Puzzle::get_final_state(width, height);
+            let mut reachable = HashSet::new();
+            let mut queue = VecDeque::from([goal.clone()]);
+            reachable.insert(goal.clone());
+            while let Some(puzzle) = queue.pop_front() {
+                for next in Node::calculate_next_states(&puzzle) {
+                    if reachable.insert(next.clone()) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+
+            let mut data: Vec<usize> = (0..width * height).collect();
+            let mut count = 0;
+            permutations(&mut data, 0, &mut |data| {
+                let puzzle = Puzzle {
+                    data: data.to_vec(),
+                    width,
+                    height,
+                };
+                assert_eq!(
+                    Puzzle::is_solvable(puzzle.clone(), &goal),
+                    reachable.contains(&puzzle),
+                    "{}",
+                    puzzle
+                );
+                count += 1;
+            });
+            assert_eq!(reachable.len() * 2, count);
+        }
+
+        fn permutations(data: &mut [usize], start: usize, check: &mut dyn FnMut(&[usize])) {
+            if start == data.len() {
+                check(data);
+                return;
+            }
+            for i in start..data.len() {
+                data.swap(start, i);
+                permutations(data, start + 1, check);
+                data.swap(start, i);
+            }
+        }
+
+        #[test]
+        fn every_odd_width_rectangle() {
+            check_every_permutation(3, 2);
+        }
+
+        #[test]
+        fn every_even_width_rectangle() {
+            check_every_permutation(2, 4);
+            check_every_permutation(4, 2);
         }
     }
 }
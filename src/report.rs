@@ -0,0 +1,184 @@
+use crate::expression::Expression;
+use crate::options::AlgorithmValues;
+use crate::puzzle::Puzzle;
+use crate::solution::{Solution, SolveError, Statistics, Stop};
+use serde_json::{json, Value};
+
+/*
+ ** the tiles row by row, the blank being 0
+ */
+fn board(puzzle: &Puzzle) -> Value {
+    json!({
+        "width": puzzle.width,
+        "height": puzzle.height,
+        "tiles": puzzle.data.chunks(puzzle.width).collect::<Vec<_>>(),
+    })
+}
+
+/*
+ ** durations in seconds
+ */
+fn statistics(statistics: &Statistics) -> Value {
+    let improvements: Vec<Value> = statistics
+        .improvements
+        .iter()
+        .map(|improvement| {
+            json!({
+                "weight": improvement.weight,
+                "moves": improvement.moves,
+                "time_elapsed": improvement.time_elapsed.as_secs_f64(),
+            })
+        })
+        .collect();
+    let parts: Vec<Value> = statistics
+        .parts
+        .iter()
+        .map(|part| json!({ "name": part.name, "expanded_states": part.expanded_states }))
+        .collect();
+
+    json!({
+        "time_elapsed": statistics.time_elapsed.as_secs_f64(),
+        "open_states": statistics.open_states,
+        "closed_states": statistics.closed_states,
+        "total_states": statistics.total_states,
+        "max_states": statistics.max_states,
+        "thresholds": statistics.thresholds,
+        "improvements": improvements,
+        "parts": parts,
+        "f_bound": statistics.progress.f_bound,
+        "best_h_score": statistics.progress.best_h_score,
+        "stopped": statistics.stopped.map(|stop| match stop {
+            Stop::Timeout => "timeout",
+            Stop::Cancelled => "cancelled",
+            Stop::OutOfMemory => "out_of_memory",
+        }),
+    })
+}
+
+/*
+ ** a whole solve as one document, for tools that would otherwise scrape the text
+ ** output : every key is always there, null when it doesn't apply
+ */
+pub fn to_json(
+    puzzle: &Puzzle,
+    goal: &Puzzle,
+    algorithm: &AlgorithmValues,
+    heuristic: &Expression,
+    result: &Result<Solution, SolveError>,
+) -> Value {
+    let (solution, error) = match result {
+        Ok(solution) => (Some(solution), None),
+        Err(error) => (None, Some(error)),
+    };
+    let partial = match error {
+        Some(SolveError::Stopped(_, statistics)) => Some(statistics.as_ref()),
+        _ => None,
+    };
+    let moves = solution.map(|solution| {
+        solution
+            .moves
+            .iter()
+            .map(|direction| direction.to_string())
+            .collect::<Vec<_>>()
+    });
+    let states = solution.map(|solution| solution.states.iter().map(board).collect::<Vec<_>>());
+
+    json!({
+        "board": board(puzzle),
+        "goal": board(goal),
+        "algorithm": algorithm.to_string().to_lowercase(),
+        "heuristic": heuristic.to_string(),
+        "solved": solution.is_some(),
+        "error": error.map(|error| error.to_string()),
+        "length": solution.map(Solution::len),
+        "moves": moves,
+        "states": states,
+        "statistics": solution
+            .map(|solution| &solution.statistics)
+            .or(partial)
+            .map(statistics),
+    })
+}
+
+#[cfg(test)]
+mod report_tests {
+    mod to_json {
+        use crate::graph::Graph;
+        use crate::heuristic::{FromGoal, Manhattan};
+        use crate::options::HeuristicValues;
+        use crate::report::*;
+        use crate::settings::Settings;
+        use serde_json::json;
+
+        fn puzzle() -> Puzzle {
+            Puzzle {
+                data: vec![0, 2, 3, 1, 8, 4, 7, 6, 5],
+                width: 3,
+                height: 3,
+            }
+        }
+
+        fn report(result: &Result<Solution, SolveError>) -> Value {
+            to_json(
+                &puzzle(),
+                &Puzzle::get_final_state(3, 3),
+                &AlgorithmValues::Astar,
+                &Expression::from(HeuristicValues::Manhattan),
+                result,
+            )
+        }
+
+        #[test]
+        fn solved() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let result = Graph::a_star(puzzle(), Manhattan::new(&goal), &Settings::default());
+            let report = report(&result);
+
+            assert_eq!(
+                report["board"],
+                json!({"width": 3, "height": 3, "tiles": [[0, 2, 3], [1, 8, 4], [7, 6, 5]]})
+            );
+            assert_eq!(
+                report["goal"]["tiles"],
+                json!([[1, 2, 3], [8, 0, 4], [7, 6, 5]])
+            );
+            assert_eq!(report["algorithm"], "astar");
+            assert_eq!(report["heuristic"], "manhattan");
+            assert_eq!(report["solved"], true);
+            assert_eq!(report["error"], Value::Null);
+            assert_eq!(report["length"], 2);
+            assert_eq!(report["moves"], json!(["D", "R"]));
+            assert_eq!(report["states"].as_array().unwrap().len(), 3);
+            assert_eq!(report["states"][2], report["goal"]);
+            assert_eq!(report["statistics"]["closed_states"], 2);
+            assert_eq!(report["statistics"]["stopped"], Value::Null);
+        }
+
+        #[test]
+        fn stopped() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let settings = Settings::default();
+            settings.cancel.cancel();
+            let result = Graph::a_star(puzzle(), Manhattan::new(&goal), &settings);
+            let report = report(&result);
+
+            assert_eq!(report["solved"], false);
+            assert_eq!(
+                report["error"],
+                "The search was cancelled before reaching the goal state"
+            );
+            assert_eq!(report["moves"], Value::Null);
+            assert_eq!(report["statistics"]["stopped"], "cancelled");
+        }
+
+        #[test]
+        fn unsolvable() {
+            let report = report(&Err(SolveError::Unsolvable));
+
+            assert_eq!(report["solved"], false);
+            assert_eq!(report["error"], "Unsolvable puzzle");
+            assert_eq!(report["length"], Value::Null);
+            assert_eq!(report["statistics"], Value::Null);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::node::Node;
+use crate::solution::{Improvement, Stop};
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// rough cost of a stored state : its node, and its entries in the hash maps with
+// their spare capacity
+const BYTES_PER_STATE: usize = 3 * mem::size_of::<Node>();
+
+/*
+ ** search parameters shared by every algorithm, each one ignores what it has no use for
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    // f = g + weight * h, 1 when None for A* and IDA*, the starting weight of ARA*,
+    // ignored by the others
+    pub weight: Option<f64>,
+    // called with every better solution ARA* finds
+    pub on_improvement: Option<fn(&Improvement)>,
+    // workers of the parallel search, one per core when None
+    pub threads: Option<usize>,
+    // states kept in memory at once, sma drops the worst ones past it, ida only keeps
+    // its path, the other searches give up
+    pub max_states: Option<usize>,
+    // same, in bytes
+    pub max_memory: Option<usize>,
+    // from the start of the search
+    pub timeout: Option<Duration>,
+    pub cancel: CancelToken,
+}
+
+/*
+ ** shared with whoever may stop the search : a Ctrl-C handler, another thread
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Settings {
+    pub fn thread_count(&self) -> usize {
+        self.threads
+            .or_else(|| {
+                thread::available_parallelism()
+                    .ok()
+                    .map(|count| count.get())
+            })
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    /*
+     ** checked by every search before each expansion
+     */
+    pub fn stop(&self, start_time: SystemTime) -> Option<Stop> {
+        if self.cancel.is_cancelled() {
+            return Some(Stop::Cancelled);
+        }
+        let elapsed = start_time.elapsed().unwrap_or_default();
+        self.timeout
+            .filter(|&timeout| elapsed >= timeout)
+            .map(|_| Stop::Timeout)
+    }
+
+    /*
+     ** the tighter of the two limits, in states
+     */
+    pub fn state_budget(&self) -> Option<usize> {
+        let from_memory = self.max_memory.map(|bytes| bytes / BYTES_PER_STATE);
+        match (self.max_states, from_memory) {
+            (Some(states), Some(memory)) => Some(states.min(memory)),
+            (states, memory) => states.or(memory),
+        }
+    }
+}
+
+/*
+ ** rounded down, a node on the optimal path then never gets above weight times its
+ ** length and the bound on the solution holds
+ */
+pub fn weighted(distance: usize, h_score: usize, weight: f64) -> usize {
+    distance + (h_score as f64 * weight) as usize
+}
@@ -0,0 +1,427 @@
+use crate::heuristic::Heuristic;
+use crate::puzzle::Puzzle;
+use crate::settings::Settings;
+use crate::solution::{Progress, Solution, SolveError, Statistics};
+use crate::state::State;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::time::SystemTime;
+
+/*
+ ** Simplified Memory-bounded A* (Russell) : a tree search that keeps at most budget
+ ** nodes. When memory is full the worst leaf (highest f, then shallowest) is dropped
+ ** and its f is remembered by its parent, which goes back in the open list to
+ ** regenerate it later if nothing better is left. The path found is the shortest one
+ ** whenever it fits in the budget.
+ */
+const INFINITY: usize = usize::MAX;
+
+struct Entry {
+    state: State,
+    distance: usize,
+    h_score: usize,
+    // never lower than the parent's (pathmax), raised to the lowest f of the
+    // successors once they are all generated
+    f_score: usize,
+    parent: Option<usize>,
+    // successors in memory
+    children: Vec<usize>,
+    // every move but the one back to the parent
+    successors: usize,
+    // successors dropped from memory and their f, given back when regenerated so
+    // that what was learnt about them isn't lost
+    forgotten: Vec<(State, usize)>,
+}
+
+pub struct Sma<H: Heuristic> {
+    nodes: Vec<Option<Entry>>,
+    free: Vec<usize>,
+    // (f, deepest first, index) of every node with a successor out of memory
+    open: BTreeSet<(usize, Reverse<usize>, usize)>,
+    pub heuristic: H,
+    pub final_state: State,
+    pub budget: usize,
+    pub stored: usize,
+    pub expanded: usize,
+    pub generated: usize,
+    pub dropped: usize,
+    pub max_states: usize,
+    pub progress: Progress,
+    pub settings: Settings,
+    pub start_time: SystemTime,
+}
+
+impl<H: Heuristic> Sma<H> {
+    pub fn new(state: Puzzle, heuristic: H, budget: usize) -> Sma<H> {
+        let state = State::from(&state);
+        let h_score = heuristic.evaluate_state(&state);
+        let final_state = State::from(heuristic.goal());
+        let mut sma = Sma {
+            nodes: vec![],
+            free: vec![],
+            open: BTreeSet::new(),
+            heuristic,
+            final_state,
+            budget: budget.max(1),
+            stored: 0,
+            expanded: 0,
+            generated: 0,
+            dropped: 0,
+            max_states: 1,
+            progress: Progress::default(),
+            settings: Settings::default(),
+            start_time: SystemTime::now(),
+        };
+        let successors = state.next_moves().len();
+        sma.insert(Entry {
+            state,
+            distance: 0,
+            h_score,
+            f_score: h_score,
+            parent: None,
+            children: vec![],
+            successors,
+            forgotten: vec![],
+        });
+
+        sma
+    }
+
+    fn entry(&self, index: usize) -> &Entry {
+        self.nodes[index].as_ref().unwrap()
+    }
+
+    fn entry_mut(&mut self, index: usize) -> &mut Entry {
+        self.nodes[index].as_mut().unwrap()
+    }
+
+    fn key(&self, index: usize) -> (usize, Reverse<usize>, usize) {
+        let entry = self.entry(index);
+        (entry.f_score, Reverse(entry.distance), index)
+    }
+
+    fn is_complete(&self, index: usize) -> bool {
+        let entry = self.entry(index);
+        entry.children.len() == entry.successors
+    }
+
+    fn insert(&mut self, entry: Entry) -> usize {
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(entry);
+                index
+            }
+            None => {
+                self.nodes.push(Some(entry));
+                self.nodes.len() - 1
+            }
+        };
+        self.open.insert(self.key(index));
+        self.stored += 1;
+        self.generated += 1;
+
+        index
+    }
+
+    fn set_f_score(&mut self, index: usize, f_score: usize) {
+        let in_open = self.open.remove(&self.key(index));
+        self.entry_mut(index).f_score = f_score;
+        if in_open {
+            self.open.insert(self.key(index));
+        }
+    }
+
+    /*
+     ** once every successor of a node was generated, no path through it is shorter
+     ** than the best of them, which may raise its ancestors too
+     */
+    fn back_up(&mut self, mut index: usize) {
+        loop {
+            let entry = self.entry(index);
+            let best = entry
+                .children
+                .iter()
+                .map(|&child| self.entry(child).f_score)
+                .chain(entry.forgotten.iter().map(|&(_, f_score)| f_score))
+                .min()
+                .unwrap_or(INFINITY);
+            if best <= entry.f_score {
+                return;
+            }
+            let parent = entry.parent;
+            self.set_f_score(index, best);
+            match parent {
+                Some(parent) => index = parent,
+                None => return,
+            }
+        }
+    }
+
+    /*
+     ** generates the successors of the node not in memory, whether new or forgotten
+     */
+    fn expand(&mut self, index: usize) {
+        self.open.remove(&self.key(index));
+        self.expanded += 1;
+        let (distance, h_score) = (self.entry(index).distance, self.entry(index).h_score);
+        self.progress.record(distance, h_score);
+
+        let entry = self.entry(index);
+        let parent_state = entry.parent.map(|parent| self.entry(parent).state.clone());
+        let known: Vec<State> = entry
+            .children
+            .iter()
+            .map(|&child| self.entry(child).state.clone())
+            .collect();
+        let (distance, h_score, f_score) = (entry.distance + 1, entry.h_score, entry.f_score);
+        let missing: Vec<_> = entry
+            .state
+            .next_moves()
+            .into_iter()
+            .filter(|(_, state)| Some(state) != parent_state.as_ref() && !known.contains(state))
+            .collect();
+
+        for (movement, state) in missing {
+            let child_h_score = self.heuristic.evaluate_move(h_score, &state, movement);
+            // a path of distance + 1 nodes, with no room left for one more
+            let remembered = self
+                .entry(index)
+                .forgotten
+                .iter()
+                .find(|(forgotten, _)| *forgotten == state)
+                .map_or(0, |&(_, f_score)| f_score);
+            let child_f_score = if state != self.final_state && distance + 1 >= self.budget {
+                INFINITY
+            } else {
+                f_score.max(distance + child_h_score).max(remembered)
+            };
+            let successors = state.next_moves().len() - 1;
+            let child = self.insert(Entry {
+                state,
+                distance,
+                h_score: child_h_score,
+                f_score: child_f_score,
+                parent: Some(index),
+                children: vec![],
+                successors,
+                forgotten: vec![],
+            });
+            self.entry_mut(index).children.push(child);
+        }
+        self.entry_mut(index).forgotten.clear();
+        self.back_up(index);
+    }
+
+    /*
+     ** drops the worst leaf, its parent keeps its f and goes back to the open list
+     */
+    fn drop_worst_leaf(&mut self) -> bool {
+        let worst = self
+            .open
+            .iter()
+            .rev()
+            .map(|&(_, _, index)| index)
+            .find(|&index| {
+                let entry = self.entry(index);
+                entry.children.is_empty() && entry.parent.is_some()
+            });
+        let Some(index) = worst else {
+            return false;
+        };
+
+        self.open.remove(&self.key(index));
+        let entry = self.nodes[index].take().unwrap();
+        self.free.push(index);
+        self.stored -= 1;
+        self.dropped += 1;
+
+        let parent = entry.parent.unwrap();
+        let was_complete = self.is_complete(parent);
+        let parent_entry = self.entry_mut(parent);
+        parent_entry.children.retain(|&child| child != index);
+        parent_entry.forgotten.push((entry.state, entry.f_score));
+        if was_complete {
+            self.open.insert(self.key(parent));
+        }
+
+        true
+    }
+
+    fn retrieve_path(&self, index: usize) -> Vec<Puzzle> {
+        let mut path = vec![];
+        let mut current = Some(index);
+        while let Some(index) = current {
+            let entry = self.entry(index);
+            path.push(Puzzle::from(&entry.state));
+            current = entry.parent;
+        }
+        path.reverse();
+
+        path
+    }
+
+    pub fn statistics(&self) -> Statistics {
+        Statistics {
+            time_elapsed: self.start_time.elapsed().unwrap(),
+            open_states: self.open.len(),
+            closed_states: self.stored - self.open.len(),
+            total_states: self.generated,
+            max_states: self.max_states,
+            progress: self.progress,
+            ..Statistics::default()
+        }
+    }
+
+    /*
+     ** the best open node is expanded until it is the goal, memory is freed after
+     ** every expansion : in between, up to 3 successors may go past the budget
+     */
+    pub fn search(&mut self) -> Result<Vec<Puzzle>, SolveError> {
+        loop {
+            let &(f_score, _, index) = self.open.first().ok_or(SolveError::NotFound)?;
+            if f_score == INFINITY {
+                return Err(SolveError::OutOfMemory);
+            }
+            if self.entry(index).state == self.final_state {
+                return Ok(self.retrieve_path(index));
+            }
+            if let Some(stop) = self.settings.stop(self.start_time) {
+                let statistics = Statistics {
+                    stopped: Some(stop),
+                    ..self.statistics()
+                };
+                return Err(SolveError::Stopped(stop, Box::new(statistics)));
+            }
+
+            self.expand(index);
+            while self.stored > self.budget && self.drop_worst_leaf() {}
+            if self.max_states < self.stored {
+                self.max_states = self.stored;
+            }
+        }
+    }
+
+    pub fn sma_star(
+        state: Puzzle,
+        heuristic: H,
+        settings: &Settings,
+    ) -> Result<Solution, SolveError> {
+        let start_time = SystemTime::now();
+        let budget = settings.state_budget().unwrap_or(INFINITY);
+        let mut sma = Sma::new(state, heuristic, budget);
+        sma.settings = settings.clone();
+        sma.start_time = start_time;
+        let path = sma.search()?;
+
+        Ok(Solution::new(path, sma.statistics()))
+    }
+}
+
+#[cfg(test)]
+mod sma_tests {
+    mod sma_star {
+        use crate::generator::{self, Method};
+        use crate::graph::Graph;
+        use crate::heuristic::{FromGoal, Manhattan, ManhattanLinearConflict};
+        use crate::node::Node;
+        use crate::sma::*;
+        use crate::solution::Stop;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        fn puzzle() -> Puzzle {
+            Puzzle {
+                data: vec![1, 8, 4, 0, 3, 5, 2, 7, 6],
+                width: 3,
+                height: 3,
+            }
+        }
+
+        fn settings(max_states: usize) -> Settings {
+            Settings {
+                max_states: Some(max_states),
+                ..Settings::default()
+            }
+        }
+
+        #[test]
+        fn shortest_path_within_budget() {
+            let goal = Puzzle::get_final_state(3, 3);
+            for budget in [20, 50, 200, 100_000] {
+                let solution =
+                    Sma::sma_star(puzzle(), Manhattan::new(&goal), &settings(budget)).unwrap();
+
+                assert_eq!(solution.len(), 15);
+                assert!(solution.statistics.max_states <= budget);
+                assert_eq!(solution.states[0], puzzle());
+                assert_eq!(*solution.states.last().unwrap(), goal);
+                assert!(solution
+                    .states
+                    .windows(2)
+                    .all(|step| Node::calculate_next_states(&step[0]).contains(&step[1])));
+            }
+        }
+
+        #[test]
+        fn drops_nodes_when_full() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let unbounded = Sma::sma_star(puzzle(), Manhattan::new(&goal), &Settings::default())
+                .unwrap()
+                .statistics;
+            let mut sma = Sma::new(puzzle(), Manhattan::new(&goal), 30);
+            let path = sma.search().unwrap();
+
+            assert_eq!(path.len(), 16);
+            assert!(unbounded.max_states > 30);
+            assert!(sma.dropped > 0);
+            assert!(sma.max_states <= 30);
+        }
+
+        #[test]
+        fn same_length_as_a_star_on_fifteen_puzzle() {
+            let goal = Puzzle::get_final_state(4, 4);
+            let mut rng = StdRng::seed_from_u64(4);
+            let start = generator::generate(&mut rng, &goal, Method::Walk(60), true);
+            let heuristic = ManhattanLinearConflict::new(&goal);
+            let expected =
+                Graph::a_star(start.clone(), heuristic.clone(), &Settings::default()).unwrap();
+            let solution = Sma::sma_star(start, heuristic, &settings(2000)).unwrap();
+
+            assert_eq!(solution.len(), expected.len());
+        }
+
+        #[test]
+        fn path_longer_than_budget() {
+            let goal = Puzzle::get_final_state(3, 3);
+
+            assert_eq!(
+                Sma::sma_star(puzzle(), Manhattan::new(&goal), &settings(8)).unwrap_err(),
+                SolveError::OutOfMemory
+            );
+        }
+
+        #[test]
+        fn cancelled() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let settings = settings(100);
+            settings.cancel.cancel();
+
+            match Sma::sma_star(puzzle(), Manhattan::new(&goal), &settings) {
+                Err(SolveError::Stopped(Stop::Cancelled, statistics)) => {
+                    assert_eq!(statistics.stopped, Some(Stop::Cancelled));
+                    assert_eq!(statistics.total_states, 1);
+                }
+                result => panic!("{:?}", result),
+            }
+        }
+
+        #[test]
+        fn already_solved() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let solution =
+                Sma::sma_star(goal.clone(), Manhattan::new(&goal), &settings(1)).unwrap();
+
+            assert!(solution.is_empty());
+        }
+    }
+}
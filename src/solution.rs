@@ -0,0 +1,279 @@
+use crate::direction::Direction;
+use crate::puzzle::Puzzle;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Statistics {
+    pub time_elapsed: Duration,
+    pub open_states: usize,
+    pub closed_states: usize,
+    pub total_states: usize,
+    pub max_states: usize,
+    pub thresholds: Vec<usize>,
+    pub improvements: Vec<Improvement>,
+    pub parts: Vec<Part>,
+    pub progress: Progress,
+    // set when the search was cut short, an anytime search still returns its best path
+    pub stopped: Option<Stop>,
+}
+
+/*
+ ** how far the search went, mostly useful when it was stopped before the goal
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Progress {
+    // highest g + h among the expanded nodes, no path is shorter with an
+    // admissible heuristic and a best-first order
+    pub f_bound: usize,
+    // lowest h among the expanded nodes, how close the search got to the goal
+    pub best_h_score: Option<usize>,
+}
+
+impl Progress {
+    pub fn record(&mut self, distance: usize, h_score: usize) {
+        self.f_bound = self.f_bound.max(distance + h_score);
+        self.best_h_score = Some(self.best_h_score.map_or(h_score, |best| best.min(h_score)));
+    }
+
+    pub fn merge(self, other: Progress) -> Progress {
+        Progress {
+            f_bound: self.f_bound.max(other.f_bound),
+            best_h_score: match (self.best_h_score, other.best_h_score) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Timeout,
+    Cancelled,
+    // past the state budget, only an anytime search stops there with a path
+    OutOfMemory,
+}
+
+/*
+ ** work done by one part of the search, a direction or a thread
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub name: String,
+    pub expanded_states: usize,
+}
+
+/*
+ ** a better solution found by an anytime search, or a tighter bound on the last one,
+ ** within weight times the optimal length
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Improvement {
+    pub weight: f64,
+    pub moves: usize,
+    pub time_elapsed: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub moves: Vec<Direction>,
+    pub states: Vec<Puzzle>,
+    pub statistics: Statistics,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveError {
+    Unsolvable,
+    WrongGoalSize,
+    NotFound,
+    OutOfMemory,
+    // with what was done until then
+    Stopped(Stop, Box<Statistics>),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Unsolvable => write!(f, "Unsolvable puzzle"),
+            SolveError::WrongGoalSize => write!(f, "The goal and the puzzle sizes differ"),
+            SolveError::NotFound => write!(
+                f,
+                "The graph has been completely explored, yet the goal state hasn't been reached"
+            ),
+            SolveError::OutOfMemory | SolveError::Stopped(Stop::OutOfMemory, _) => {
+                write!(f, "The memory limit has been reached before the goal state")
+            }
+            SolveError::Stopped(Stop::Timeout, _) => {
+                write!(f, "The search timed out before reaching the goal state")
+            }
+            SolveError::Stopped(Stop::Cancelled, _) => {
+                write!(f, "The search was cancelled before reaching the goal state")
+            }
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+impl Solution {
+    pub fn new(states: Vec<Puzzle>, statistics: Statistics) -> Solution {
+        let moves = states
+            .windows(2)
+            .map(|step| Direction::between(&step[0], &step[1]).expect("Path is not continuous"))
+            .collect();
+
+        Solution {
+            moves,
+            states,
+            statistics,
+        }
+    }
+
+    /*
+     ** the direction the blank moves at each step, "ULDR..."
+     */
+    pub fn move_string(&self) -> String {
+        self.moves
+            .iter()
+            .map(|direction| direction.to_string())
+            .collect()
+    }
+
+    /*
+     ** the tile slid into the blank at each step
+     */
+    pub fn tiles(&self) -> Vec<usize> {
+        self.states
+            .windows(2)
+            .map(|step| {
+                let (x, y) = step[1].get_position(0);
+                step[0].get_value(x, y)
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod solution_tests {
+    mod new {
+        use crate::direction::Direction;
+        use crate::puzzle::Puzzle;
+        use crate::solution::*;
+
+        fn statistics() -> Statistics {
+            Statistics {
+                time_elapsed: Duration::from_secs(0),
+                open_states: 0,
+                closed_states: 0,
+                total_states: 0,
+                max_states: 0,
+                thresholds: vec![],
+                improvements: vec![],
+                parts: vec![],
+                progress: Progress::default(),
+                stopped: None,
+            }
+        }
+
+        #[test]
+        fn moves_from_states() {
+            let states = vec![
+                Puzzle {
+                    data: vec![0, 2, 3, 1, 8, 4, 7, 6, 5],
+                    width: 3,
+                    height: 3,
+                },
+                Puzzle {
+                    data: vec![1, 2, 3, 0, 8, 4, 7, 6, 5],
+                    width: 3,
+                    height: 3,
+                },
+                Puzzle::get_final_state(3, 3),
+            ];
+            let solution = Solution::new(states, statistics());
+
+            assert_eq!(solution.moves, vec![Direction::Down, Direction::Right]);
+            assert_eq!(solution.len(), 2);
+        }
+
+        #[test]
+        fn move_string_and_tiles() {
+            let states = vec![
+                Puzzle {
+                    data: vec![0, 2, 3, 1, 8, 4, 7, 6, 5],
+                    width: 3,
+                    height: 3,
+                },
+                Puzzle {
+                    data: vec![1, 2, 3, 0, 8, 4, 7, 6, 5],
+                    width: 3,
+                    height: 3,
+                },
+                Puzzle::get_final_state(3, 3),
+            ];
+            let solution = Solution::new(states, statistics());
+
+            assert_eq!(solution.move_string(), "DR");
+            assert_eq!(solution.tiles(), vec![1, 8]);
+        }
+
+        #[test]
+        fn already_solved() {
+            let solution = Solution::new(vec![Puzzle::get_final_state(3, 3)], statistics());
+
+            assert!(solution.is_empty());
+            assert_eq!(solution.move_string(), "");
+            assert!(solution.tiles().is_empty());
+        }
+
+        #[test]
+        #[should_panic]
+        fn not_continuous() {
+            Solution::new(
+                vec![
+                    Puzzle {
+                        data: vec![0, 2, 3, 1, 8, 4, 7, 6, 5],
+                        width: 3,
+                        height: 3,
+                    },
+                    Puzzle::get_final_state(3, 3),
+                ],
+                statistics(),
+            );
+        }
+    }
+
+    mod progress {
+        use crate::solution::Progress;
+
+        #[test]
+        fn record_and_merge() {
+            let mut forward = Progress::default();
+            forward.record(0, 12);
+            forward.record(5, 9);
+            let mut backward = Progress::default();
+            backward.record(3, 7);
+
+            assert_eq!(forward.f_bound, 14);
+            assert_eq!(forward.best_h_score, Some(9));
+            assert_eq!(
+                forward.merge(backward),
+                Progress {
+                    f_bound: 14,
+                    best_h_score: Some(7),
+                }
+            );
+            assert_eq!(Progress::default().merge(backward), backward);
+        }
+    }
+}
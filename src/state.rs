@@ -0,0 +1,372 @@
+use crate::direction::{Direction, Move};
+use crate::puzzle::Puzzle;
+use std::fmt;
+
+/*
+ ** 4 bits per tile fit a whole board of up to 16 tiles in a single u64,
+ ** bigger boards fall back to one byte per tile, then to two.
+ ** The parser and the options reject boards past MAX_TILES.
+ */
+const SMALL_MAX_TILES: usize = 16;
+const LARGE_MAX_TILES: usize = 256;
+pub const MAX_TILES: usize = 1 << 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum State {
+    Small {
+        tiles: u64,
+        width: u8,
+        height: u8,
+        blank: u8,
+    },
+    Large {
+        tiles: Box<[u8]>,
+        width: u8,
+        height: u8,
+        blank: u8,
+    },
+    Huge {
+        tiles: Box<[u16]>,
+        width: u16,
+        height: u16,
+        blank: u16,
+    },
+}
+
+impl From<&Puzzle> for State {
+    fn from(puzzle: &Puzzle) -> State {
+        let len = puzzle.data.len();
+        let blank = puzzle.data.iter().position(|&value| value == 0).unwrap();
+        let (width, height) = (puzzle.width, puzzle.height);
+
+        if len <= SMALL_MAX_TILES {
+            let tiles = puzzle
+                .data
+                .iter()
+                .enumerate()
+                .fold(0u64, |tiles, (i, &value)| tiles | (value as u64) << (4 * i));
+            State::Small {
+                tiles,
+                width: width as u8,
+                height: height as u8,
+                blank: blank as u8,
+            }
+        } else if len <= LARGE_MAX_TILES {
+            let tiles = puzzle.data.iter().map(|&value| value as u8).collect();
+            State::Large {
+                tiles,
+                width: width as u8,
+                height: height as u8,
+                blank: blank as u8,
+            }
+        } else if len <= MAX_TILES {
+            let tiles = puzzle.data.iter().map(|&value| value as u16).collect();
+            State::Huge {
+                tiles,
+                width: width as u16,
+                height: height as u16,
+                blank: blank as u16,
+            }
+        } else {
+            panic!("Boards of more than {} tiles cannot be packed", MAX_TILES);
+        }
+    }
+}
+
+impl From<&State> for Puzzle {
+    fn from(state: &State) -> Puzzle {
+        let (width, height) = (state.width(), state.height());
+        Puzzle {
+            data: (0..width * height).map(|i| state.get(i)).collect(),
+            width,
+            height,
+        }
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Puzzle::from(self))
+    }
+}
+
+impl State {
+    pub fn width(&self) -> usize {
+        match self {
+            State::Small { width, .. } | State::Large { width, .. } => *width as usize,
+            State::Huge { width, .. } => *width as usize,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self {
+            State::Small { height, .. } | State::Large { height, .. } => *height as usize,
+            State::Huge { height, .. } => *height as usize,
+        }
+    }
+
+    pub fn blank(&self) -> usize {
+        match self {
+            State::Small { blank, .. } | State::Large { blank, .. } => *blank as usize,
+            State::Huge { blank, .. } => *blank as usize,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> usize {
+        match self {
+            State::Small { tiles, .. } => ((tiles >> (4 * index)) & 0xf) as usize,
+            State::Large { tiles, .. } => tiles[index] as usize,
+            State::Huge { tiles, .. } => tiles[index] as usize,
+        }
+    }
+
+    pub fn move_blank(&self, target: usize) -> State {
+        match self {
+            State::Small {
+                tiles,
+                width,
+                height,
+                blank,
+            } => {
+                let value = (tiles >> (4 * target)) & 0xf;
+                State::Small {
+                    tiles: (tiles & !(0xf << (4 * target))) | value << (4 * *blank as usize),
+                    width: *width,
+                    height: *height,
+                    blank: target as u8,
+                }
+            }
+            State::Large {
+                tiles,
+                width,
+                height,
+                blank,
+            } => {
+                let mut tiles = tiles.clone();
+                tiles.swap(*blank as usize, target);
+                State::Large {
+                    tiles,
+                    width: *width,
+                    height: *height,
+                    blank: target as u8,
+                }
+            }
+            State::Huge {
+                tiles,
+                width,
+                height,
+                blank,
+            } => {
+                let mut tiles = tiles.clone();
+                tiles.swap(*blank as usize, target);
+                State::Huge {
+                    tiles,
+                    width: *width,
+                    height: *height,
+                    blank: target as u16,
+                }
+            }
+        }
+    }
+
+    pub fn next_moves(&self) -> Vec<(Move, State)> {
+        // same order as Node::calculate_next_states : up, down, left, right
+        let width = self.width();
+        let blank = self.blank();
+        let (x, y) = (blank % width, blank / width);
+        let mut targets = vec![];
+
+        if y != 0 {
+            targets.push((Direction::Up, blank - width));
+        }
+        if y != self.height() - 1 {
+            targets.push((Direction::Down, blank + width));
+        }
+        if x != 0 {
+            targets.push((Direction::Left, blank - 1));
+        }
+        if x != width - 1 {
+            targets.push((Direction::Right, blank + 1));
+        }
+
+        targets
+            .into_iter()
+            .map(|(direction, target)| {
+                let movement = Move {
+                    tile: self.get(target),
+                    direction,
+                };
+                (movement, self.move_blank(target))
+            })
+            .collect()
+    }
+
+    pub fn next_states(&self) -> Vec<State> {
+        self.next_moves()
+            .into_iter()
+            .map(|(_, state)| state)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod state_tests {
+    mod from {
+        use crate::puzzle::Puzzle;
+        use crate::state::State;
+
+        #[test]
+        fn small_round_trip() {
+            let puzzle = Puzzle {
+                data: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0],
+                width: 4,
+                height: 4,
+            };
+            let state = State::from(&puzzle);
+
+            match state {
+                State::Small { blank, .. } => assert_eq!(blank, 15),
+                _ => panic!("a 4x4 board should fit in a u64"),
+            }
+            assert_eq!(Puzzle::from(&state), puzzle);
+        }
+
+        #[test]
+        fn large_round_trip() {
+            let puzzle = Puzzle::get_final_state(10, 10);
+            let state = State::from(&puzzle);
+
+            match state {
+                State::Large { blank, .. } => assert_eq!(blank, 54),
+                _ => panic!("a 10x10 board should fit in a byte per tile"),
+            }
+            assert_eq!(Puzzle::from(&state), puzzle);
+        }
+
+        #[test]
+        fn huge_round_trip() {
+            let puzzle = Puzzle::get_final_state(17, 17);
+            let state = State::from(&puzzle);
+
+            match state {
+                State::Huge { blank, .. } => assert_eq!(blank, 8 * 17 + 8),
+                _ => panic!("a 17x17 board cannot fit in a byte per tile"),
+            }
+            assert_eq!(Puzzle::from(&state), puzzle);
+            assert_eq!(state.next_states().len(), 4);
+        }
+
+        #[test]
+        #[should_panic]
+        fn too_large() {
+            State::from(&Puzzle::get_final_state(257, 256)).width();
+        }
+    }
+
+    mod move_blank {
+        use crate::puzzle::Puzzle;
+        use crate::state::State;
+
+        #[test]
+        fn small() {
+            let state = State::from(&Puzzle {
+                data: vec![1, 2, 3, 8, 0, 4, 7, 6, 5],
+                width: 3,
+                height: 3,
+            });
+            let expected = State::from(&Puzzle {
+                data: vec![1, 0, 3, 8, 2, 4, 7, 6, 5],
+                width: 3,
+                height: 3,
+            });
+
+            assert_eq!(state.move_blank(1), expected);
+            assert_eq!(state.move_blank(1).blank(), 1);
+        }
+
+        #[test]
+        fn large() {
+            let mut data: Vec<usize> = (0..25).collect();
+            let state = State::from(&Puzzle {
+                data: data.clone(),
+                width: 5,
+                height: 5,
+            });
+            data.swap(0, 5);
+
+            assert_eq!(
+                state.move_blank(5),
+                State::from(&Puzzle {
+                    data,
+                    width: 5,
+                    height: 5,
+                })
+            );
+        }
+    }
+
+    mod next_moves {
+        use crate::direction::{Direction, Move};
+        use crate::puzzle::Puzzle;
+        use crate::state::State;
+
+        #[test]
+        fn tile_and_direction() {
+            let state = State::from(&Puzzle {
+                data: vec![1, 2, 3, 0, 8, 4, 7, 6, 5],
+                width: 3,
+                height: 3,
+            });
+            let moves: Vec<Move> = state
+                .next_moves()
+                .into_iter()
+                .map(|(movement, _)| movement)
+                .collect();
+
+            assert_eq!(
+                moves,
+                vec![
+                    Move {
+                        tile: 1,
+                        direction: Direction::Up
+                    },
+                    Move {
+                        tile: 7,
+                        direction: Direction::Down
+                    },
+                    Move {
+                        tile: 8,
+                        direction: Direction::Right
+                    },
+                ]
+            );
+        }
+    }
+
+    mod next_states {
+        use crate::node::Node;
+        use crate::puzzle::Puzzle;
+        use crate::state::State;
+
+        #[test]
+        fn same_as_calculate_next_states() {
+            for puzzle in [
+                Puzzle::get_final_state(3, 3),
+                Puzzle {
+                    data: vec![0, 1, 2, 3, 4, 5, 6, 8, 7],
+                    width: 3,
+                    height: 3,
+                },
+                Puzzle::get_final_state(5, 5),
+            ] {
+                let next_states: Vec<Puzzle> = State::from(&puzzle)
+                    .next_states()
+                    .iter()
+                    .map(Puzzle::from)
+                    .collect();
+
+                assert_eq!(next_states, Node::calculate_next_states(&puzzle));
+            }
+        }
+    }
+}
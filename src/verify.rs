@@ -0,0 +1,166 @@
+use crate::direction::Direction;
+use crate::node::Node;
+use crate::puzzle::Puzzle;
+use std::fmt;
+
+/*
+ ** why a move of the sequence could not be played
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalMove {
+    // not one of U, D, L or R
+    UnknownLetter(char),
+    // would take the blank off the board
+    OffBoard(Direction),
+}
+
+/*
+ ** a move sequence played from the start, up to its first illegal move
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    // legal moves played
+    pub moves: usize,
+    pub final_state: Puzzle,
+    pub reaches_goal: bool,
+    // position of the move in the sequence, from 1, and why it was rejected
+    pub illegal: Option<(usize, IllegalMove)>,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IllegalMove::UnknownLetter(letter) => {
+                write!(f, "{:?} is not a move, expected U, D, L or R", letter)
+            }
+            IllegalMove::OffBoard(direction) => {
+                write!(f, "{:?} takes the blank off the board", direction)
+            }
+        }
+    }
+}
+
+/*
+ ** the blank swapped with its neighbour in that direction, None on the edge
+ */
+fn play(puzzle: &Puzzle, direction: Direction) -> Option<Puzzle> {
+    let (x, y) = puzzle.get_position(0);
+    let (x_next, y_next) = match direction {
+        Direction::Up => (Some(x), y.checked_sub(1)),
+        Direction::Down => (Some(x), Some(y + 1).filter(|&y| y < puzzle.height)),
+        Direction::Left => (x.checked_sub(1), Some(y)),
+        Direction::Right => (Some(x + 1).filter(|&x| x < puzzle.width), Some(y)),
+    };
+
+    Some(Node::swap_two_positions(puzzle, x, y, x_next?, y_next?))
+}
+
+/*
+ ** moves are the directions the blank goes, as printed by --output moves, case and
+ ** whitespace or commas between them don't matter
+ */
+pub fn replay(start: &Puzzle, goal: &Puzzle, moves: &str) -> Replay {
+    let mut state = start.clone();
+    let mut played = 0;
+    let mut illegal = None;
+
+    let letters = moves
+        .chars()
+        .filter(|&letter| !letter.is_whitespace() && letter != ',');
+    for letter in letters {
+        let next = Direction::from_letter(letter)
+            .ok_or(IllegalMove::UnknownLetter(letter))
+            .and_then(|direction| play(&state, direction).ok_or(IllegalMove::OffBoard(direction)));
+        match next {
+            Ok(next) => {
+                state = next;
+                played += 1;
+            }
+            Err(reason) => {
+                illegal = Some((played + 1, reason));
+                break;
+            }
+        }
+    }
+
+    Replay {
+        moves: played,
+        reaches_goal: illegal.is_none() && state == *goal,
+        final_state: state,
+        illegal,
+    }
+}
+
+#[cfg(test)]
+mod verify_tests {
+    mod replay {
+        use crate::direction::Direction;
+        use crate::verify::*;
+
+        fn puzzle() -> Puzzle {
+            Puzzle {
+                data: vec![0, 2, 3, 1, 8, 4, 7, 6, 5],
+                width: 3,
+                height: 3,
+            }
+        }
+
+        #[test]
+        fn reaches_goal() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let replay = replay(&puzzle(), &goal, "DR");
+
+            assert!(replay.reaches_goal);
+            assert_eq!(replay.moves, 2);
+            assert_eq!(replay.final_state, goal);
+            assert_eq!(replay.illegal, None);
+        }
+
+        #[test]
+        fn separators_and_case() {
+            let goal = Puzzle::get_final_state(3, 3);
+
+            assert!(replay(&puzzle(), &goal, " d, r\n").reaches_goal);
+        }
+
+        #[test]
+        fn wrong_end_state() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let replay = replay(&puzzle(), &goal, "DRU");
+
+            assert!(!replay.reaches_goal);
+            assert_eq!(replay.moves, 3);
+            assert_eq!(replay.illegal, None);
+        }
+
+        #[test]
+        fn off_board() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let replay = replay(&puzzle(), &goal, "DLR");
+
+            assert!(!replay.reaches_goal);
+            assert_eq!(replay.moves, 1);
+            assert_eq!(
+                replay.illegal,
+                Some((2, IllegalMove::OffBoard(Direction::Left)))
+            );
+        }
+
+        #[test]
+        fn unknown_letter() {
+            let goal = Puzzle::get_final_state(3, 3);
+            let replay = replay(&puzzle(), &goal, "DxR");
+
+            assert_eq!(replay.moves, 1);
+            assert_eq!(replay.illegal, Some((2, IllegalMove::UnknownLetter('x'))));
+        }
+
+        #[test]
+        fn nothing_to_play() {
+            let goal = Puzzle::get_final_state(3, 3);
+
+            assert!(replay(&goal, &goal, "").reaches_goal);
+            assert!(!replay(&puzzle(), &goal, "").reaches_goal);
+        }
+    }
+}
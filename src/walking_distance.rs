@@ -0,0 +1,294 @@
+use crate::heuristic::{FromGoal, Heuristic};
+use crate::puzzle::Puzzle;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/*
+ ** Walking Distance (Ken'ichiro Takahashi) : the vertical part only looks at how many
+ ** tiles of each goal row sit in each row, and counts the moves needed to sort them
+ ** when the blank can swap with any tile of an adjacent row. The horizontal part does
+ ** the same with columns. Vertical and horizontal moves are disjoint, so both add up.
+ */
+// past this number of configurations a direction falls back to its manhattan distance
+const MAX_CONFIGURATIONS: usize = 1 << 20;
+
+/*
+ ** counts[line * lines + class] is the number of tiles of the class (goal line)
+ ** lying on the line, the blank is kept apart
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Configuration {
+    counts: Vec<usize>,
+    blank: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Table {
+    lines: usize,
+    line_length: usize,
+    distances: Option<HashMap<u128, u8>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WalkingDistance {
+    pub goal: Puzzle,
+    // goal (x, y) of every tile
+    goal_positions: Vec<(usize, usize)>,
+    pub vertical: Table,
+    pub horizontal: Table,
+}
+
+impl Table {
+    fn key(&self, configuration: &Configuration) -> Option<u128> {
+        let radix = self.line_length as u128 + 1;
+        configuration
+            .counts
+            .iter()
+            .try_fold(0u128, |key, &count| {
+                key.checked_mul(radix)?.checked_add(count as u128)
+            })?
+            .checked_mul(self.lines as u128)?
+            .checked_add(configuration.blank as u128)
+    }
+
+    fn next_configurations(&self, configuration: &Configuration) -> Vec<Configuration> {
+        let lines = self.lines;
+        let blank = configuration.blank;
+        let mut next = vec![];
+
+        for line in [blank.wrapping_sub(1), blank + 1] {
+            if line >= lines {
+                continue;
+            }
+            for class in 0..lines {
+                if configuration.counts[line * lines + class] != 0 {
+                    let mut counts = configuration.counts.clone();
+                    counts[line * lines + class] -= 1;
+                    counts[blank * lines + class] += 1;
+                    next.push(Configuration {
+                        counts,
+                        blank: line,
+                    });
+                }
+            }
+        }
+
+        next
+    }
+
+    /*
+     ** breadth-first search from the goal configuration, every line sorted
+     */
+    fn build(lines: usize, line_length: usize, goal_blank: usize) -> Table {
+        let mut table = Table {
+            lines,
+            line_length,
+            distances: None,
+        };
+        let mut counts = vec![0; lines * lines];
+        for line in 0..lines {
+            counts[line * lines + line] = line_length - (line == goal_blank) as usize;
+        }
+        let start = Configuration {
+            counts,
+            blank: goal_blank,
+        };
+        let Some(key) = table.key(&start) else {
+            return table;
+        };
+
+        let mut distances = HashMap::new();
+        distances.insert(key, 0u8);
+        let mut frontier = vec![start];
+        let mut distance = 0u8;
+        while !frontier.is_empty() {
+            distance = distance.saturating_add(1);
+            let mut next_frontier = vec![];
+            for configuration in &frontier {
+                for next in table.next_configurations(configuration) {
+                    let Some(key) = table.key(&next) else {
+                        return table;
+                    };
+                    if let Entry::Vacant(entry) = distances.entry(key) {
+                        entry.insert(distance);
+                        next_frontier.push(next);
+                    }
+                }
+            }
+            if distances.len() > MAX_CONFIGURATIONS {
+                return table;
+            }
+            frontier = next_frontier;
+        }
+
+        table.distances = Some(distances);
+        table
+    }
+
+    pub fn configurations(&self) -> Option<usize> {
+        self.distances.as_ref().map(|distances| distances.len())
+    }
+}
+
+impl WalkingDistance {
+    pub fn build(goal: &Puzzle) -> WalkingDistance {
+        let mut goal_positions = vec![(0, 0); goal.data.len()];
+        for (position, &value) in goal.data.iter().enumerate() {
+            goal_positions[value] = (position % goal.width, position / goal.width);
+        }
+        let (blank_x, blank_y) = goal_positions[0];
+
+        WalkingDistance {
+            goal: goal.clone(),
+            goal_positions,
+            vertical: Table::build(goal.height, goal.width, blank_y),
+            horizontal: Table::build(goal.width, goal.height, blank_x),
+        }
+    }
+
+    fn part(&self, table: &Table, puzzle: &Puzzle, vertical: bool) -> usize {
+        let lines = table.lines;
+        let mut counts = vec![0; lines * lines];
+        let mut blank = 0;
+        let mut manhattan = 0;
+
+        for (position, &value) in puzzle.data.iter().enumerate() {
+            let (x, y) = (position % puzzle.width, position / puzzle.width);
+            let (goal_x, goal_y) = self.goal_positions[value];
+            let (line, class) = if vertical { (y, goal_y) } else { (x, goal_x) };
+            if value == 0 {
+                blank = line;
+            } else {
+                counts[line * lines + class] += 1;
+                manhattan += line.abs_diff(class);
+            }
+        }
+
+        let configuration = Configuration { counts, blank };
+        table
+            .distances
+            .as_ref()
+            .zip(table.key(&configuration))
+            .and_then(|(distances, key)| distances.get(&key))
+            .map_or(manhattan, |&distance| distance as usize)
+    }
+}
+
+impl FromGoal for WalkingDistance {
+    fn new(goal: &Puzzle) -> WalkingDistance {
+        WalkingDistance::build(goal)
+    }
+}
+
+impl Heuristic for WalkingDistance {
+    fn name(&self) -> &str {
+        "walking"
+    }
+
+    fn goal(&self) -> &Puzzle {
+        &self.goal
+    }
+
+    fn toward(&self, goal: &Puzzle) -> Option<Box<dyn Heuristic>> {
+        Some(Box::new(WalkingDistance::build(goal)))
+    }
+
+    fn evaluate(&self, puzzle: &Puzzle) -> usize {
+        self.part(&self.vertical, puzzle, true) + self.part(&self.horizontal, puzzle, false)
+    }
+}
+
+#[cfg(test)]
+mod walking_distance_tests {
+    mod build {
+        use crate::puzzle::Puzzle;
+        use crate::walking_distance::WalkingDistance;
+
+        #[test]
+        fn fifteen_puzzle_table() {
+            let walking_distance = WalkingDistance::build(&Puzzle::get_final_state(4, 4));
+
+            assert_eq!(walking_distance.vertical.configurations(), Some(24964));
+            assert_eq!(walking_distance.horizontal.configurations(), Some(24964));
+        }
+
+        #[test]
+        fn rectangle_tables() {
+            let walking_distance = WalkingDistance::build(&Puzzle::get_final_state(4, 2));
+
+            assert_eq!(walking_distance.vertical.lines, 2);
+            assert_eq!(walking_distance.horizontal.lines, 4);
+        }
+    }
+
+    mod evaluate {
+        use crate::generator::{self, Method};
+        use crate::heuristic::{FromGoal, Heuristic, Manhattan};
+        use crate::node::Node;
+        use crate::puzzle::Puzzle;
+        use crate::walking_distance::WalkingDistance;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use std::collections::{HashMap, VecDeque};
+
+        // returns how many states are strictly above manhattan
+        fn check_every_state(goal: Puzzle) -> usize {
+            let walking_distance = WalkingDistance::build(&goal);
+            let manhattan_distance = Manhattan::new(&goal);
+            let mut distances = HashMap::new();
+            let mut queue = VecDeque::from([goal.clone()]);
+            let mut stronger = 0;
+            distances.insert(goal.clone(), 0);
+            while let Some(puzzle) = queue.pop_front() {
+                let distance = distances[&puzzle];
+                let cost = walking_distance.evaluate(&puzzle);
+                assert!(cost <= distance, "{}", puzzle);
+                let manhattan = manhattan_distance.evaluate(&puzzle);
+                assert!(cost >= manhattan, "{}", puzzle);
+                if cost > manhattan {
+                    stronger += 1;
+                }
+                for next in Node::calculate_next_states(&puzzle) {
+                    if !distances.contains_key(&next) {
+                        distances.insert(next.clone(), distance + 1);
+                        queue.push_back(next);
+                    }
+                }
+            }
+
+            stronger
+        }
+
+        #[test]
+        fn goal_is_zero() {
+            let goal = Puzzle::get_final_state(4, 4);
+
+            assert_eq!(WalkingDistance::build(&goal).evaluate(&goal), 0);
+        }
+
+        // lines of more than 255 tiles
+        #[test]
+        fn long_lines() {
+            let goal = Puzzle::get_final_state(2, 256);
+            let walking_distance = WalkingDistance::build(&goal);
+            let manhattan_distance = Manhattan::new(&goal);
+            let mut rng = StdRng::seed_from_u64(256);
+            let puzzle = generator::generate(&mut rng, &goal, Method::Walk(6), true);
+
+            assert_eq!(walking_distance.horizontal.lines, 2);
+            assert_eq!(walking_distance.evaluate(&goal), 0);
+            assert!(walking_distance.evaluate(&puzzle) <= 6);
+            assert!(walking_distance.evaluate(&puzzle) >= manhattan_distance.evaluate(&puzzle));
+        }
+
+        #[test]
+        fn admissible_and_at_least_manhattan_on_every_eight_puzzle() {
+            assert!(check_every_state(Puzzle::get_final_state(3, 3)) > 0);
+        }
+
+        #[test]
+        fn admissible_and_at_least_manhattan_on_every_rectangle() {
+            assert!(check_every_state(Puzzle::get_final_state(4, 2)) > 0);
+        }
+    }
+}